@@ -1,36 +1,23 @@
 use std::sync::Arc;
 
 use borsh::{BorshDeserialize, BorshSerialize};
-use wire_message::{wire_message, WireMessage};
+use wire_message::wire_message;
 use zk_primitives::Element;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 #[wire_message]
 pub(super) enum KeyFormat {
+    #[upgrade_with = key_v1_to_v2]
     V1(Element),
     V2(KeyV2),
 }
 
-impl WireMessage for KeyFormat {
-    type Ctx = ();
-    type Err = core::convert::Infallible;
-
-    fn version(&self) -> u64 {
-        match self {
-            Self::V1(_) => 1,
-            Self::V2(_) => 2,
-        }
-    }
-
-    fn upgrade_once(self, _ctx: &mut Self::Ctx) -> Result<Self, wire_message::Error> {
-        match self {
-            Self::V1(element) => Ok(Self::V2(KeyV2::Element(element))),
-            Self::V2(_) => Err(Self::max_version_error()),
-        }
-    }
+#[allow(clippy::unnecessary_wraps)]
+fn key_v1_to_v2(element: Element, _ctx: &mut ()) -> Result<KeyV2, wire_message::Error> {
+    Ok(KeyV2::Element(element))
 }
 
-#[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize)]
 pub(super) enum KeyV2 {
     Element(Element),
     KnownHash { left: Element, right: Element },
@@ -39,30 +26,17 @@ pub(super) enum KeyV2 {
 #[derive(Debug, Clone)]
 #[wire_message]
 pub(super) enum ValueFormat<T: Clone> {
+    #[upgrade_with = value_v1_to_v2]
     V1(Arc<T>),
     V2(ValueV2<T>),
 }
 
-impl<T> WireMessage for ValueFormat<T>
-where
-    T: Clone + BorshSerialize + BorshDeserialize + Send + Sync + 'static,
-{
-    type Ctx = ();
-    type Err = core::convert::Infallible;
-
-    fn version(&self) -> u64 {
-        match self {
-            Self::V1(_) => 1,
-            Self::V2(_) => 2,
-        }
-    }
-
-    fn upgrade_once(self, _ctx: &mut Self::Ctx) -> Result<Self, wire_message::Error> {
-        match self {
-            Self::V1(metadata) => Ok(Self::V2(ValueV2::Metadata(metadata))),
-            Self::V2(_) => Err(Self::max_version_error()),
-        }
-    }
+#[allow(clippy::unnecessary_wraps)]
+fn value_v1_to_v2<T: Clone>(
+    metadata: Arc<T>,
+    _ctx: &mut (),
+) -> Result<ValueV2<T>, wire_message::Error> {
+    Ok(ValueV2::Metadata(metadata))
 }
 
 #[derive(Debug, Clone, BorshSerialize, BorshDeserialize)]
@@ -2,10 +2,14 @@ use std::{collections::HashSet, path::PathBuf};
 
 use tempdir::TempDir;
 use test_strategy::proptest;
+use wire_message::test_api::Golden;
 
 use crate::{batch, Batch};
 
-use super::*;
+use super::{
+    format::{KeyFormat, KeyV2},
+    *,
+};
 
 fn setup_path() -> (TempDir, PathBuf) {
     let dir = TempDir::new("smirk_db_test").unwrap();
@@ -96,3 +100,22 @@ fn insert_batch_works(batch_1: Batch<64, i32>, mut batch_2: Batch<64, i32>) {
         assert!(loaded.tree().contains_element(element));
     }
 }
+
+#[test]
+fn key_format_fixtures() {
+    Golden::new(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/key_format"))
+        .sample_upgrading_to(
+            "element",
+            KeyFormat::V1(Element::new(1)),
+            KeyFormat::V2(KeyV2::Element(Element::new(1))),
+        )
+        .sample("element", KeyFormat::V2(KeyV2::Element(Element::new(2))))
+        .sample(
+            "known_hash",
+            KeyFormat::V2(KeyV2::KnownHash {
+                left: Element::new(3),
+                right: Element::new(4),
+            }),
+        )
+        .check(&mut ());
+}
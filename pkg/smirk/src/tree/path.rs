@@ -1,6 +1,6 @@
 use std::iter::zip;
 
-use crate::{Element, Lsb, Tree};
+use crate::{Element, Lsb, MerkleProof, ProofError, Tree};
 
 use super::tree_repr::Node;
 
@@ -186,6 +186,53 @@ impl<const DEPTH: usize> Path<DEPTH> {
     pub fn actual_root_hash(&self) -> Element {
         self.root_hash
     }
+
+    /// Convert this path into a [`MerkleProof`] for the slot of [`Path::element`], containing
+    /// `leaf`
+    ///
+    /// To prove that the element is present, `leaf` should be the element itself. To prove that
+    /// it is absent, `leaf` should be [`Element::NULL_HASH`]
+    ///
+    /// ```rust
+    /// # use smirk::*;
+    /// let tree: Tree<64, _> = smirk! { 1, 2, 3 };
+    ///
+    /// let path = tree.path_for(Element::new(1));
+    /// let proof = path.to_merkle_proof(Element::new(1));
+    /// assert_eq!(proof.verify(tree.root_hash()), Ok(()));
+    ///
+    /// let path = tree.path_for(Element::new(4));
+    /// let proof = path.to_merkle_proof(Element::NULL_HASH);
+    /// assert_eq!(proof.verify(tree.root_hash()), Ok(()));
+    /// ```
+    #[must_use]
+    pub fn to_merkle_proof(&self, leaf: Element) -> MerkleProof<DEPTH> {
+        let siblings = self.siblings_deepest_first().to_vec();
+
+        // a path always has `DEPTH - 1` siblings, so this can't fail
+        MerkleProof::for_key(self.element(), leaf, siblings).unwrap()
+    }
+}
+
+/// Convert an inclusion proof into a [`Path`] for its leaf
+///
+/// The root hash of the resulting [`Path`] is the root hash computed from the proof. This fails if
+/// the directions of the proof don't match the bits of its leaf
+impl<const DEPTH: usize> TryFrom<MerkleProof<DEPTH>> for Path<DEPTH> {
+    type Error = ProofError;
+
+    fn try_from(proof: MerkleProof<DEPTH>) -> Result<Self, Self::Error> {
+        proof.check_key(proof.leaf())?;
+
+        let mut siblings = [Element::NULL_HASH; DEPTH];
+        siblings[0..DEPTH - 1].copy_from_slice(proof.siblings());
+        *siblings.last_mut().unwrap() = proof.leaf();
+
+        Ok(Path {
+            siblings,
+            root_hash: proof.compute_root(),
+        })
+    }
 }
 
 impl<const DEPTH: usize, V, C> Tree<DEPTH, V, C> {
@@ -316,6 +363,30 @@ mod tests {
         assert_eq!(path.siblings_deepest_first().len(), 63);
     }
 
+    #[proptest]
+    fn merkle_proof_verifies(tree: Tree<64, i32>, element: Element) {
+        let path = tree.path_for(element);
+
+        let leaf = match tree.contains_element(element) {
+            true => element,
+            false => Element::NULL_HASH,
+        };
+
+        let proof = path.to_merkle_proof(leaf);
+        assert_eq!(proof.verify(tree.root_hash()), Ok(()));
+        assert_eq!(proof.compute_root(), path.compute_root_hash(leaf));
+    }
+
+    #[proptest]
+    fn merkle_proof_path_round_trip(tree: Tree<64, i32>, element: Element) {
+        let path = tree.path_for(element);
+        let proof = path.to_merkle_proof(element);
+
+        let path_again = Path::try_from(proof.clone()).unwrap();
+        assert_eq!(path_again.siblings, path.siblings);
+        assert_eq!(path_again.to_merkle_proof(element), proof);
+    }
+
     #[proptest]
     fn lsb_and_siblings_same_size(tree: Tree<16, i32>, element: Element) {
         let path = tree.path_for(element);
@@ -1,13 +1,38 @@
 use proc_macro2::TokenStream;
 use quote::{quote, quote_spanned};
-use syn::{parse_macro_input, spanned::Spanned, Data, DeriveInput};
+use syn::{
+    parse_macro_input, parse_quote, spanned::Spanned, Data, DataEnum, DeriveInput, Expr, Fields,
+    Generics, LitStr, Meta, Type,
+};
 
 #[proc_macro_attribute]
 pub fn wire_message(
-    _attr: proc_macro::TokenStream,
+    attr: proc_macro::TokenStream,
     tokens: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
-    let input = parse_macro_input!(tokens as DeriveInput);
+    let mut args = Args::default();
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("ctx") {
+            args.ctx = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("err") {
+            args.err = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("type_id") {
+            args.type_id = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("manual") {
+            args.manual = true;
+            Ok(())
+        } else {
+            Err(meta.error(
+                "unsupported `#[wire_message]` argument, expected `ctx`, `err`, `type_id` or `manual`",
+            ))
+        }
+    });
+    parse_macro_input!(attr with parser);
+
+    let mut input = parse_macro_input!(tokens as DeriveInput);
 
     let extra_attrs = quote::quote! {
         #[derive(::borsh::BorshSerialize, ::borsh::BorshDeserialize, ::wire_message::strum_macros::EnumCount)]
@@ -21,15 +46,48 @@ pub fn wire_message(
 
     let check_enum = check_enum(&input);
 
+    let framed_impl = match &args.type_id {
+        Some(type_id) => framed_impl(&input, type_id),
+        None => quote! {},
+    };
+
+    let wire_message_impl = match &mut input.data {
+        Data::Enum(data) => {
+            let upgrades = take_variant_attrs(data, "upgrade_with");
+            let downgrades = take_variant_attrs(data, "downgrade_with");
+
+            match args.manual {
+                true => check_manual(&input, &args, &upgrades, &downgrades)
+                    .err()
+                    .map_or_else(TokenStream::new, syn::Error::into_compile_error),
+                false => wire_message_impl(&input, args, &upgrades, &downgrades)
+                    .unwrap_or_else(syn::Error::into_compile_error),
+            }
+        }
+        _ => quote! {},
+    };
+
     quote::quote! {
         #check_enum
         #extra_attrs
         #enum_try_as
         #input
+        #wire_message_impl
+        #framed_impl
     }
     .into()
 }
 
+/// Arguments passed to the `#[wire_message(...)]` attribute
+#[derive(Default)]
+struct Args {
+    ctx: Option<Type>,
+    err: Option<Type>,
+    type_id: Option<LitStr>,
+    /// Don't generate the `WireMessage` impl, because it is written by hand
+    manual: bool,
+}
+
 // EnumTryAs doesn't work with generics
 fn has_generics(input: &DeriveInput) -> bool {
     !input.generics.params.is_empty()
@@ -46,3 +104,241 @@ fn check_enum(input: &DeriveInput) -> TokenStream {
         }
     }
 }
+
+/// With `manual`, the `WireMessage` impl is written by hand, so the arguments and attributes that
+/// configure the generated impl would be silently ignored
+fn check_manual(
+    input: &DeriveInput,
+    args: &Args,
+    upgrades: &[Option<syn::Result<Expr>>],
+    downgrades: &[Option<syn::Result<Expr>>],
+) -> syn::Result<()> {
+    let no_attrs = upgrades.iter().chain(downgrades).all(Option::is_none);
+
+    match args.ctx.is_none() && args.err.is_none() && no_attrs {
+        true => Ok(()),
+        false => Err(syn::Error::new_spanned(
+            &input.ident,
+            "`manual` can't be combined with `ctx`, `err`, `#[upgrade_with]` or `#[downgrade_with]`",
+        )),
+    }
+}
+
+/// Remove the `#[<name> = ...]` attributes (e.g. `#[upgrade_with = ...]`) from each variant, since
+/// they aren't real attributes, and return them in variant order
+fn take_variant_attrs(data: &mut DataEnum, name: &str) -> Vec<Option<syn::Result<Expr>>> {
+    data.variants
+        .iter_mut()
+        .map(|variant| {
+            let index = variant
+                .attrs
+                .iter()
+                .position(|attr| attr.path().is_ident(name))?;
+
+            let attr = variant.attrs.remove(index);
+
+            let result = match attr.meta {
+                Meta::NameValue(name_value) => Ok(name_value.value),
+                meta => Err(syn::Error::new_spanned(
+                    meta,
+                    format!("expected `#[{name} = path::to::function]`"),
+                )),
+            };
+
+            Some(result)
+        })
+        .collect()
+}
+
+/// Generate the `WireMessage` impl, with `version`, `upgrade_once` and `downgrade_once` derived
+/// from the order of the variants and their `#[upgrade_with = ...]` and `#[downgrade_with = ...]`
+/// attributes
+fn wire_message_impl(
+    input: &DeriveInput,
+    args: Args,
+    upgrades: &[Option<syn::Result<Expr>>],
+    downgrades: &[Option<syn::Result<Expr>>],
+) -> syn::Result<TokenStream> {
+    let Data::Enum(data) = &input.data else {
+        unreachable!("only called on enums");
+    };
+
+    if data.variants.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "`#[wire_message]` enums must have at least one variant",
+        ));
+    }
+
+    let name = &input.ident;
+    let last = data.variants.len() - 1;
+
+    let mut version_arms = Vec::new();
+    let mut upgrade_arms = Vec::new();
+
+    for (index, (variant, upgrade)) in data.variants.iter().zip(upgrades).enumerate() {
+        let ident = &variant.ident;
+        let version = index as u64 + 1;
+
+        version_arms.push(quote! { Self::#ident { .. } => #version, });
+
+        match (index == last, upgrade) {
+            (true, None) => {
+                upgrade_arms
+                    .push(quote! { Self::#ident { .. } => Err(Self::max_version_error()), });
+            }
+            (true, Some(_)) => {
+                return Err(syn::Error::new_spanned(
+                    variant,
+                    "the last variant is the max version, so it can't have `#[upgrade_with]`",
+                ));
+            }
+            (false, None) => {
+                return Err(syn::Error::new_spanned(
+                    variant,
+                    "every variant except the last must have `#[upgrade_with = ...]`, or use \
+                     `#[wire_message(manual)]` to implement `WireMessage` by hand",
+                ));
+            }
+            (false, Some(upgrade)) => {
+                let upgrade = upgrade.as_ref().map_err(Clone::clone)?;
+                check_single_field(variant)?;
+                check_single_field(&data.variants[index + 1])?;
+
+                let next = &data.variants[index + 1].ident;
+
+                upgrade_arms.push(quote! {
+                    Self::#ident(inner) => #upgrade(inner, ctx).map(Self::#next),
+                });
+            }
+        }
+    }
+
+    let downgrade_once = downgrade_once(data, downgrades)?;
+
+    let ctx = args.ctx.unwrap_or_else(|| parse_quote!(()));
+    let err = args
+        .err
+        .unwrap_or_else(|| parse_quote!(::core::convert::Infallible));
+
+    let generics = wire_message_generics(input);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::wire_message::WireMessage for #name #ty_generics #where_clause {
+            type Ctx = #ctx;
+            type Err = #err;
+
+            fn version(&self) -> u64 {
+                match self {
+                    #(#version_arms)*
+                }
+            }
+
+            #[allow(unused_variables)]
+            fn upgrade_once(self, ctx: &mut Self::Ctx) -> ::core::result::Result<Self, ::wire_message::Error<Self::Err>> {
+                match self {
+                    #(#upgrade_arms)*
+                }
+            }
+
+            #downgrade_once
+        }
+    })
+}
+
+/// Generate `downgrade_once` if any variant has a `#[downgrade_with = ...]` attribute
+///
+/// Variants without one are considered lossy to downgrade
+fn downgrade_once(
+    data: &DataEnum,
+    downgrades: &[Option<syn::Result<Expr>>],
+) -> syn::Result<TokenStream> {
+    if downgrades.iter().all(Option::is_none) {
+        return Ok(quote! {});
+    }
+
+    let mut arms = Vec::new();
+
+    for (index, (variant, downgrade)) in data.variants.iter().zip(downgrades).enumerate() {
+        let ident = &variant.ident;
+
+        match (index, downgrade) {
+            (0, Some(_)) => {
+                return Err(syn::Error::new_spanned(
+                    variant,
+                    "the first variant is the min version, so it can't have `#[downgrade_with]`",
+                ));
+            }
+            (_, None) => {
+                arms.push(quote! { Self::#ident { .. } => Err(self.lossy_downgrade_error()), });
+            }
+            (_, Some(downgrade)) => {
+                let downgrade = downgrade.as_ref().map_err(Clone::clone)?;
+                check_single_field(variant)?;
+                check_single_field(&data.variants[index - 1])?;
+
+                let previous = &data.variants[index - 1].ident;
+
+                arms.push(quote! {
+                    Self::#ident(inner) => #downgrade(inner, ctx).map(Self::#previous),
+                });
+            }
+        }
+    }
+
+    Ok(quote! {
+        #[allow(unused_variables)]
+        fn downgrade_once(self, ctx: &mut Self::Ctx) -> ::core::result::Result<Self, ::wire_message::Error<Self::Err>> {
+            match self {
+                #(#arms)*
+            }
+        }
+    })
+}
+
+/// Upgrade (and downgrade) functions take the single field of a variant and return the single
+/// field of the next (or previous) variant
+fn check_single_field(variant: &syn::Variant) -> syn::Result<()> {
+    match &variant.fields {
+        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => Ok(()),
+        _ => Err(syn::Error::new_spanned(
+            variant,
+            "variants with `#[upgrade_with]` or `#[downgrade_with]` (and the variants they convert \
+             to) must have exactly one unnamed field",
+        )),
+    }
+}
+
+/// Generate the `Framed` impl with the given type identifier
+fn framed_impl(input: &DeriveInput, type_id: &LitStr) -> TokenStream {
+    let name = &input.ident;
+    let generics = wire_message_generics(input);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics ::wire_message::Framed for #name #ty_generics #where_clause {
+            const TYPE_ID: &'static str = #type_id;
+        }
+    }
+}
+
+/// The generics of the input, with the bounds required by `WireMessage` added to each type
+/// parameter
+fn wire_message_generics(input: &DeriveInput) -> Generics {
+    let mut generics = input.generics.clone();
+
+    for param in generics.type_params_mut() {
+        param
+            .bounds
+            .push(parse_quote!(::wire_message::borsh::BorshSerialize));
+        param
+            .bounds
+            .push(parse_quote!(::wire_message::borsh::BorshDeserialize));
+        param.bounds.push(parse_quote!(::core::marker::Send));
+        param.bounds.push(parse_quote!(::core::marker::Sync));
+        param.bounds.push(parse_quote!('static));
+    }
+
+    generics
+}
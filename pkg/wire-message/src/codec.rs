@@ -0,0 +1,280 @@
+use std::marker::PhantomData;
+
+use bytes::{Buf, BufMut, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::{Error, ErrorKind, WireMessage};
+
+/// The default value of [`WireMessageCodec::max_size`] (16 MiB)
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+/// The size of the big-endian `u32` length prefix before every message
+const LENGTH_PREFIX_LEN: usize = 4;
+
+/// A [`tokio_util::codec`] codec for streaming [`WireMessage`]s
+///
+/// Each message is written as a big-endian `u32` length prefix, followed by the bytes from
+/// [`WireMessage::to_bytes`]. Messages larger than [`WireMessageCodec::max_size`] are rejected in
+/// both directions, and a peer announcing a larger message is rejected before any of it is
+/// buffered.
+///
+/// Every decoded message is upgraded to [`WireMessage::MAX_VERSION`] with the context owned by the
+/// codec, so a stream of messages can be consumed without checking versions.
+///
+/// Use it with [`FramedRead`][tokio_util::codec::FramedRead],
+/// [`FramedWrite`][tokio_util::codec::FramedWrite] or [`Framed`][tokio_util::codec::Framed] to get
+/// a `Stream` or `Sink` of messages:
+/// ```rust
+/// # use bytes::BytesMut;
+/// # use tokio_util::codec::{Decoder, Encoder};
+/// # use wire_message::{wire_message, Error, WireMessage, WireMessageCodec};
+/// #[derive(Debug, PartialEq)]
+/// #[wire_message]
+/// enum Message {
+///     #[upgrade_with = v1_to_v2]
+///     V1(u32),
+///     V2(u64),
+/// }
+///
+/// fn v1_to_v2(v1: u32, _ctx: &mut ()) -> Result<u64, Error> {
+///     Ok(v1.into())
+/// }
+///
+/// let mut codec = WireMessageCodec::<Message>::new(());
+/// let mut buffer = BytesMut::new();
+///
+/// codec.encode(Message::V1(1), &mut buffer).unwrap();
+///
+/// assert_eq!(codec.decode(&mut buffer).unwrap(), Some(Message::V2(1)));
+/// assert_eq!(codec.decode(&mut buffer).unwrap(), None);
+/// ```
+pub struct WireMessageCodec<M: WireMessage> {
+    ctx: M::Ctx,
+    max_size: usize,
+    _message: PhantomData<fn() -> M>,
+}
+
+impl<M: WireMessage> WireMessageCodec<M> {
+    /// Create a codec which upgrades decoded messages with `ctx`, and allows messages of up to
+    /// [`DEFAULT_MAX_MESSAGE_SIZE`] bytes
+    #[must_use]
+    pub fn new(ctx: M::Ctx) -> Self {
+        Self {
+            ctx,
+            max_size: DEFAULT_MAX_MESSAGE_SIZE,
+            _message: PhantomData,
+        }
+    }
+
+    /// Set the maximum size of a message in bytes (not including the length prefix)
+    ///
+    /// Values greater than [`u32::MAX`] are treated as [`u32::MAX`], since that is the largest
+    /// length that can be written in the prefix
+    #[must_use]
+    pub fn with_max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size.min(u32::MAX as usize);
+        self
+    }
+
+    /// The maximum size of a message in bytes (not including the length prefix)
+    #[inline]
+    #[must_use]
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    /// The context used to upgrade decoded messages
+    #[inline]
+    pub fn ctx(&self) -> &M::Ctx {
+        &self.ctx
+    }
+
+    /// The context used to upgrade decoded messages
+    #[inline]
+    pub fn ctx_mut(&mut self) -> &mut M::Ctx {
+        &mut self.ctx
+    }
+
+    /// Consume the codec, returning the context
+    #[inline]
+    pub fn into_ctx(self) -> M::Ctx {
+        self.ctx
+    }
+
+    fn check_size<T>(&self, size: usize) -> Result<(), Error<T>> {
+        match size > self.max_size {
+            true => Err(Error::from_kind(ErrorKind::MessageTooLarge {
+                size,
+                max_size: self.max_size,
+            })),
+            false => Ok(()),
+        }
+    }
+}
+
+impl<M> Default for WireMessageCodec<M>
+where
+    M: WireMessage,
+    M::Ctx: Default,
+{
+    fn default() -> Self {
+        Self::new(M::Ctx::default())
+    }
+}
+
+impl<M: WireMessage> Decoder for WireMessageCodec<M> {
+    type Item = M;
+    type Error = Error<M::Err>;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<M>, Error<M::Err>> {
+        let Some(prefix) = src.get(..LENGTH_PREFIX_LEN) else {
+            return Ok(None);
+        };
+
+        let size = u32::from_be_bytes(prefix.try_into().unwrap()) as usize;
+        self.check_size(size)?;
+
+        let frame_len = LENGTH_PREFIX_LEN + size;
+
+        if src.len() < frame_len {
+            src.reserve(frame_len - src.len());
+            return Ok(None);
+        }
+
+        src.advance(LENGTH_PREFIX_LEN);
+        let bytes = src.split_to(size);
+
+        M::from_bytes(&bytes)
+            .map_err(Error::cast)?
+            .upgrade(&mut self.ctx)
+            .map(Some)
+    }
+}
+
+impl<M: WireMessage> Encoder<&M> for WireMessageCodec<M> {
+    type Error = Error;
+
+    fn encode(&mut self, item: &M, dst: &mut BytesMut) -> Result<(), Error> {
+        let bytes = item.to_bytes()?;
+        self.check_size(bytes.len())?;
+
+        // `max_size` is at most `u32::MAX`, so this can't truncate
+        #[allow(clippy::cast_possible_truncation)]
+        let size = bytes.len() as u32;
+
+        dst.reserve(LENGTH_PREFIX_LEN + bytes.len());
+        dst.put_u32(size);
+        dst.extend_from_slice(&bytes);
+
+        Ok(())
+    }
+}
+
+impl<M: WireMessage> Encoder<M> for WireMessageCodec<M> {
+    type Error = Error;
+
+    fn encode(&mut self, item: M, dst: &mut BytesMut) -> Result<(), Error> {
+        self.encode(&item, dst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(
+        borsh::BorshSerialize, borsh::BorshDeserialize, strum_macros::EnumCount, Debug, PartialEq,
+    )]
+    enum TestMsg {
+        V1(u32),
+        V2(u64),
+    }
+
+    impl WireMessage for TestMsg {
+        /// The number of upgrades performed
+        type Ctx = usize;
+        type Err = core::convert::Infallible;
+
+        fn version(&self) -> u64 {
+            match self {
+                Self::V1(_) => 1,
+                Self::V2(_) => 2,
+            }
+        }
+
+        fn upgrade_once(self, ctx: &mut Self::Ctx) -> Result<Self, Error> {
+            match self {
+                Self::V1(value) => {
+                    *ctx += 1;
+                    Ok(Self::V2(value.into()))
+                }
+                Self::V2(_) => Err(Self::max_version_error()),
+            }
+        }
+    }
+
+    #[test]
+    fn round_trip_upgrades() {
+        let mut codec = WireMessageCodec::<TestMsg>::default();
+        let mut buffer = BytesMut::new();
+
+        codec.encode(TestMsg::V1(1), &mut buffer).unwrap();
+        codec.encode(&TestMsg::V2(2), &mut buffer).unwrap();
+
+        assert_eq!(codec.decode(&mut buffer).unwrap(), Some(TestMsg::V2(1)));
+        assert_eq!(codec.decode(&mut buffer).unwrap(), Some(TestMsg::V2(2)));
+        assert_eq!(codec.decode(&mut buffer).unwrap(), None);
+        assert!(buffer.is_empty());
+        assert_eq!(*codec.ctx(), 1);
+    }
+
+    #[test]
+    fn partial_frames_wait_for_more_bytes() {
+        let mut codec = WireMessageCodec::<TestMsg>::default();
+        let mut encoded = BytesMut::new();
+        codec.encode(TestMsg::V2(123), &mut encoded).unwrap();
+
+        let mut buffer = BytesMut::new();
+
+        for &byte in &encoded[..encoded.len() - 1] {
+            buffer.put_u8(byte);
+            assert_eq!(codec.decode(&mut buffer).unwrap(), None);
+        }
+
+        buffer.put_u8(*encoded.last().unwrap());
+        assert_eq!(codec.decode(&mut buffer).unwrap(), Some(TestMsg::V2(123)));
+    }
+
+    #[test]
+    fn rejects_large_messages() {
+        let mut codec = WireMessageCodec::<TestMsg>::default().with_max_size(4);
+        let mut buffer = BytesMut::new();
+
+        // a `V2` is 9 bytes (1 byte tag + 8 byte u64)
+        let error = codec.encode(TestMsg::V2(1), &mut buffer).unwrap_err();
+        assert!(error.is_message_too_large());
+        assert!(buffer.is_empty());
+
+        // the announced size is rejected before the message arrives
+        buffer.put_u32(1024);
+        let error = codec.decode(&mut buffer).unwrap_err();
+        assert_eq!(
+            error.kind(),
+            &ErrorKind::MessageTooLarge {
+                size: 1024,
+                max_size: 4,
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_message_is_a_deserialize_error() {
+        let mut codec = WireMessageCodec::<TestMsg>::default();
+        let mut buffer = BytesMut::new();
+
+        buffer.put_u32(1);
+        buffer.put_u8(7);
+
+        assert!(codec.decode(&mut buffer).unwrap_err().is_deserialize());
+    }
+}
@@ -1,5 +1,7 @@
 use std::{backtrace::Backtrace, fmt};
 
+use crate::FrameTypeId;
+
 pub struct Error<T = core::convert::Infallible> {
     pub(crate) backtrace: Backtrace,
     pub(crate) kind: ErrorKind<T>,
@@ -19,10 +21,77 @@ pub enum ErrorKind<T = core::convert::Infallible> {
     Serialize,
     Deserialize,
     Upgrade(T),
-    MaxVersion { version: u64 },
+    MaxVersion {
+        version: u64,
+    },
+    /// The bytes were not a valid frame (e.g. the magic bytes were missing, or the frame was
+    /// truncated)
+    InvalidFrame,
+    /// The frame contained a different message type
+    TypeMismatch {
+        expected: &'static str,
+        found: FrameTypeId,
+    },
+    /// The checksum of the frame didn't match its contents
+    ChecksumMismatch {
+        expected: u32,
+        found: u32,
+    },
+    /// Downgrading from `version` would lose information
+    LossyDowngrade {
+        version: u64,
+    },
+    /// The version is not known to this message type (i.e. it's 0 or greater than the max
+    /// version)
+    UnsupportedVersion {
+        version: u64,
+        max_version: u64,
+    },
+    /// An I/O error occurred while reading or writing a message
+    Io,
+    /// The message was larger than the maximum size allowed by a codec
+    MessageTooLarge {
+        size: usize,
+        max_size: usize,
+    },
 }
 
+// Matching on a copy of the kind must keep working, so every variant has to stay `Copy`
+static_assertions::assert_impl_all!(ErrorKind: Copy);
+
 impl<T> Error<T> {
+    pub(crate) fn from_kind(kind: ErrorKind<T>) -> Self {
+        Self {
+            backtrace: Backtrace::capture(),
+            kind,
+            source: None,
+        }
+    }
+
+    /// Construct an [`ErrorKind::Upgrade`] error from the custom error of an `upgrade` function
+    ///
+    /// ```rust
+    /// # use wire_message::Error;
+    /// let error = Error::upgrade("missing row");
+    ///
+    /// assert!(error.is_upgrade());
+    /// assert_eq!(error.upgrade_error(), Some(&"missing row"));
+    /// ```
+    #[must_use]
+    pub fn upgrade(err: T) -> Self {
+        Self::from_kind(ErrorKind::Upgrade(err))
+    }
+
+    /// The custom error of an `upgrade` function, if this is an [`ErrorKind::Upgrade`] error
+    #[inline]
+    #[must_use]
+    pub fn upgrade_error(&self) -> Option<&T> {
+        match &self.kind {
+            ErrorKind::Upgrade(err) => Some(err),
+            _ => None,
+        }
+    }
+
     #[must_use]
     pub fn kind(&self) -> &ErrorKind<T> {
         &self.kind
@@ -60,33 +129,163 @@ impl<T> Error<T> {
     pub fn is_max_version(&self) -> bool {
         matches!(&self.kind, ErrorKind::MaxVersion { .. })
     }
+
+    /// Is this a lossy downgrade error
+    #[inline]
+    #[must_use]
+    pub fn is_lossy_downgrade(&self) -> bool {
+        matches!(&self.kind, ErrorKind::LossyDowngrade { .. })
+    }
+
+    /// Is this an invalid frame error
+    #[inline]
+    #[must_use]
+    pub fn is_invalid_frame(&self) -> bool {
+        matches!(&self.kind, ErrorKind::InvalidFrame)
+    }
+
+    /// Is this a type mismatch error
+    #[inline]
+    #[must_use]
+    pub fn is_type_mismatch(&self) -> bool {
+        matches!(&self.kind, ErrorKind::TypeMismatch { .. })
+    }
+
+    /// Is this a checksum mismatch error
+    #[inline]
+    #[must_use]
+    pub fn is_checksum_mismatch(&self) -> bool {
+        matches!(&self.kind, ErrorKind::ChecksumMismatch { .. })
+    }
+
+    /// Is this an unsupported version error
+    #[inline]
+    #[must_use]
+    pub fn is_unsupported_version(&self) -> bool {
+        matches!(&self.kind, ErrorKind::UnsupportedVersion { .. })
+    }
+
+    /// Is this an I/O error
+    #[inline]
+    #[must_use]
+    pub fn is_io(&self) -> bool {
+        matches!(&self.kind, ErrorKind::Io)
+    }
+
+    /// Is this a message-too-large error
+    #[inline]
+    #[must_use]
+    pub fn is_message_too_large(&self) -> bool {
+        matches!(&self.kind, ErrorKind::MessageTooLarge { .. })
+    }
+}
+
+impl Error {
+    /// Convert an error which can't contain a custom upgrade error into an [`Error<T>`]
+    ///
+    /// This is useful when combining errors from (for example) [`WireMessage::from_bytes`] with
+    /// errors from [`WireMessage::upgrade`]
+    ///
+    /// [`WireMessage::from_bytes`]: crate::WireMessage::from_bytes
+    /// [`WireMessage::upgrade`]: crate::WireMessage::upgrade
+    #[must_use]
+    pub fn cast<T>(self) -> Error<T> {
+        let kind = match self.kind {
+            ErrorKind::Serialize => ErrorKind::Serialize,
+            ErrorKind::Deserialize => ErrorKind::Deserialize,
+            ErrorKind::Upgrade(never) => match never {},
+            ErrorKind::MaxVersion { version } => ErrorKind::MaxVersion { version },
+            ErrorKind::InvalidFrame => ErrorKind::InvalidFrame,
+            ErrorKind::TypeMismatch { expected, found } => {
+                ErrorKind::TypeMismatch { expected, found }
+            }
+            ErrorKind::ChecksumMismatch { expected, found } => {
+                ErrorKind::ChecksumMismatch { expected, found }
+            }
+            ErrorKind::LossyDowngrade { version } => ErrorKind::LossyDowngrade { version },
+            ErrorKind::UnsupportedVersion {
+                version,
+                max_version,
+            } => ErrorKind::UnsupportedVersion {
+                version,
+                max_version,
+            },
+            ErrorKind::Io => ErrorKind::Io,
+            ErrorKind::MessageTooLarge { size, max_size } => {
+                ErrorKind::MessageTooLarge { size, max_size }
+            }
+        };
+
+        Error {
+            backtrace: self.backtrace,
+            kind,
+            source: self.source,
+        }
+    }
 }
 
-impl<T> fmt::Display for Error<T>
-where
-    T: fmt::Display,
-{
+impl<T> From<std::io::Error> for Error<T> {
+    fn from(e: std::io::Error) -> Self {
+        Self {
+            backtrace: Backtrace::capture(),
+            kind: ErrorKind::Io,
+            source: Some(e),
+        }
+    }
+}
+
+/// The underlying I/O error and the custom error of an [`ErrorKind::Upgrade`] are not part of the
+/// message, since they are the [`source`][std::error::Error::source] of the error
+impl<T> fmt::Display for Error<T> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match &self.kind {
-            ErrorKind::Serialize => write!(f, "serialize error: {}", self.source.as_ref().unwrap()),
-            ErrorKind::Deserialize => {
-                write!(f, "deserialize error: {}", self.source.as_ref().unwrap())
-            }
+            ErrorKind::Serialize => write!(f, "serialize error"),
+            ErrorKind::Deserialize => write!(f, "deserialize error"),
             ErrorKind::MaxVersion { version } => write!(
                 f,
                 "tried to upgrade, but the version was {version}, which is the max version"
             ),
-            ErrorKind::Upgrade(e) => write!(f, "failed to upgrade: {e}"),
+            ErrorKind::Upgrade(_) => write!(f, "failed to upgrade"),
+            ErrorKind::InvalidFrame => write!(f, "invalid frame"),
+            ErrorKind::TypeMismatch { expected, found } => write!(
+                f,
+                "expected a frame containing `{expected}`, but it contained `{found}`"
+            ),
+            ErrorKind::ChecksumMismatch { expected, found } => write!(
+                f,
+                "frame checksum mismatch: expected {expected:#010x}, found {found:#010x}"
+            ),
+            ErrorKind::LossyDowngrade { version } => write!(
+                f,
+                "cannot downgrade from version {version} without losing information"
+            ),
+            ErrorKind::UnsupportedVersion {
+                version,
+                max_version,
+            } => write!(
+                f,
+                "unsupported version {version} (the max version is {max_version})"
+            ),
+            ErrorKind::Io => write!(f, "io error"),
+            ErrorKind::MessageTooLarge { size, max_size } => write!(
+                f,
+                "message of {size} bytes exceeds the max size of {max_size} bytes"
+            ),
         }
     }
 }
 
-impl<T: fmt::Debug + fmt::Display> std::error::Error for Error<T> {
+/// Implemented when the custom error of an [`ErrorKind::Upgrade`] is itself an error, since it is
+/// the [`source`][std::error::Error::source]. Otherwise (e.g. for `Error<String>`) use
+/// [`Error::upgrade_error`] to get it
+impl<T: std::error::Error + 'static> std::error::Error for Error<T> {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        None
-    }
-
-    fn cause(&self) -> Option<&dyn std::error::Error> {
-        self.source.as_ref().map(|i| i as &dyn std::error::Error)
+        match &self.kind {
+            ErrorKind::Upgrade(e) => Some(e),
+            _ => self
+                .source
+                .as_ref()
+                .map(|e| e as &(dyn std::error::Error + 'static)),
+        }
     }
 }
@@ -0,0 +1,304 @@
+use std::fmt;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::{Error, ErrorKind, WireMessage};
+
+/// The magic bytes at the start of every frame
+pub const FRAME_MAGIC: [u8; 4] = *b"WMSG";
+
+/// The version of the frame layout itself (not the message inside it)
+const FRAME_FORMAT: u8 = 1;
+
+/// The size of the checksum at the end of every frame
+const CHECKSUM_LEN: usize = 4;
+
+/// [`WireMessage`]s that can be written inside a self-describing frame
+///
+/// [`WireMessage::to_bytes`] emits bare borsh, so bytes of one message type can happily
+/// deserialize as another type with a compatible layout, and corruption is only detected if borsh
+/// happens to fail. A frame adds:
+///  - the magic bytes [`FRAME_MAGIC`]
+///  - a stable type identifier ([`Framed::TYPE_ID`])
+///  - the version of the message
+///  - a CRC-32 checksum of the whole frame
+///
+/// The easiest way to implement this trait is with the `type_id` argument of
+/// [`wire_message`][crate::wire_message]:
+/// ```rust
+/// # use wire_message::{wire_message, Framed, WireMessage};
+/// #[wire_message(type_id = "example/Message")]
+/// enum Message {
+///     V1(u32),
+/// }
+///
+/// let bytes = Message::V1(123).to_framed_bytes().unwrap();
+/// let message = Message::from_framed_bytes(&bytes).unwrap();
+///
+/// assert!(matches!(message, Message::V1(123)));
+/// ```
+pub trait Framed: WireMessage {
+    /// A stable identifier for this message type
+    ///
+    /// This is written into every frame, and checked when reading a frame, so it must never
+    /// change once frames have been persisted or sent to peers
+    const TYPE_ID: &'static str;
+
+    /// Serialize this instance inside a frame
+    fn to_framed_bytes(&self) -> Result<Vec<u8>, Error> {
+        let frame = Frame {
+            magic: FRAME_MAGIC,
+            format: FRAME_FORMAT,
+            type_id: Self::TYPE_ID.to_string(),
+            version: self.version(),
+            payload: self.to_bytes()?,
+        };
+
+        #[allow(clippy::disallowed_methods)]
+        let mut bytes = borsh::to_vec(&frame).map_err(|e| Error {
+            kind: ErrorKind::Serialize,
+            backtrace: std::backtrace::Backtrace::capture(),
+            source: Some(e),
+        })?;
+
+        let checksum = crc32(&bytes);
+        bytes.extend_from_slice(&checksum.to_le_bytes());
+
+        Ok(bytes)
+    }
+
+    /// Deserialize an instance of `Self` from a frame created by [`Framed::to_framed_bytes`]
+    ///
+    /// The magic bytes, checksum, type identifier and version are all checked before the message
+    /// itself is deserialized. Note that, like [`WireMessage::from_bytes`], this does not upgrade
+    /// the message
+    fn from_framed_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() < FRAME_MAGIC.len() + CHECKSUM_LEN || !bytes.starts_with(&FRAME_MAGIC) {
+            return Err(Error::from_kind(ErrorKind::InvalidFrame));
+        }
+
+        let (body, checksum) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+        let expected = u32::from_le_bytes(checksum.try_into().unwrap());
+        let found = crc32(body);
+
+        if expected != found {
+            return Err(Error::from_kind(ErrorKind::ChecksumMismatch {
+                expected,
+                found,
+            }));
+        }
+
+        #[allow(clippy::disallowed_methods)]
+        let frame = Frame::try_from_slice(body).map_err(|e| Error {
+            kind: ErrorKind::Deserialize,
+            backtrace: std::backtrace::Backtrace::capture(),
+            source: Some(e),
+        })?;
+
+        if frame.format != FRAME_FORMAT {
+            return Err(Error::from_kind(ErrorKind::InvalidFrame));
+        }
+
+        if frame.type_id != Self::TYPE_ID {
+            return Err(Error::from_kind(ErrorKind::TypeMismatch {
+                expected: Self::TYPE_ID,
+                found: FrameTypeId::new(&frame.type_id),
+            }));
+        }
+
+        if frame.version == 0 || frame.version > Self::MAX_VERSION {
+            return Err(Self::unsupported_version_error(frame.version));
+        }
+
+        let message = Self::from_bytes(&frame.payload)?;
+
+        // the version in the header must agree with the payload
+        if message.version() != frame.version {
+            return Err(Error::from_kind(ErrorKind::InvalidFrame));
+        }
+
+        Ok(message)
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct Frame {
+    magic: [u8; 4],
+    format: u8,
+    type_id: String,
+    version: u64,
+    payload: Vec<u8>,
+}
+
+/// The type identifier of a frame that contained a different message type
+///
+/// The identifier comes from untrusted bytes, so it is stored inline and truncated (at a `char`
+/// boundary) to [`FrameTypeId::MAX_LEN`] bytes. This keeps [`ErrorKind`] `Copy` and small.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FrameTypeId {
+    bytes: [u8; Self::MAX_LEN],
+    len: u8,
+}
+
+impl FrameTypeId {
+    /// The number of bytes of the identifier that are kept, so the identifier and its length fit
+    /// in 32 bytes
+    pub const MAX_LEN: usize = 31;
+
+    /// Copy up to [`FrameTypeId::MAX_LEN`] bytes of `type_id`
+    #[must_use]
+    pub fn new(type_id: &str) -> Self {
+        let mut len = type_id.len().min(Self::MAX_LEN);
+        while !type_id.is_char_boundary(len) {
+            len -= 1;
+        }
+
+        let mut bytes = [0; Self::MAX_LEN];
+        bytes[..len].copy_from_slice(&type_id.as_bytes()[..len]);
+
+        Self {
+            bytes,
+            // `len` is at most `MAX_LEN`
+            len: len as u8,
+        }
+    }
+
+    /// The identifier, which is truncated if it was longer than [`FrameTypeId::MAX_LEN`] bytes
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        // `new` only copies whole chars of a `str`
+        std::str::from_utf8(&self.bytes[..usize::from(self.len)]).unwrap_or_default()
+    }
+}
+
+impl fmt::Debug for FrameTypeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for FrameTypeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The lookup table for [`crc32`], generated at compile time
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+
+        while bit < 8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0xedb8_8320,
+                _ => crc >> 1,
+            };
+            bit += 1;
+        }
+
+        table[i] = crc;
+        i += 1;
+    }
+
+    table
+};
+
+/// The CRC-32 (IEEE) checksum of `bytes`
+fn crc32(bytes: &[u8]) -> u32 {
+    let crc = bytes.iter().fold(!0u32, |crc, &byte| {
+        CRC32_TABLE[((crc ^ u32::from(byte)) & 0xff) as usize] ^ (crc >> 8)
+    });
+
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(BorshSerialize, BorshDeserialize, strum_macros::EnumCount, Debug, PartialEq)]
+    enum TestMsg<T> {
+        V1(T),
+    }
+
+    impl<T: BorshSerialize + BorshDeserialize + Send + Sync + 'static> WireMessage for TestMsg<T> {
+        type Ctx = ();
+        type Err = core::convert::Infallible;
+
+        fn version(&self) -> u64 {
+            1
+        }
+
+        fn upgrade_once(self, _ctx: &mut Self::Ctx) -> Result<Self, Error> {
+            Err(Self::max_version_error())
+        }
+    }
+
+    impl Framed for TestMsg<i32> {
+        const TYPE_ID: &'static str = "test/TestMsg<i32>";
+    }
+
+    impl Framed for TestMsg<u8> {
+        const TYPE_ID: &'static str = "test/TestMsg<u8>";
+    }
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn round_trip() {
+        let message = TestMsg::V1(123);
+        let bytes = message.to_framed_bytes().unwrap();
+
+        assert!(bytes.starts_with(&FRAME_MAGIC));
+        assert_eq!(TestMsg::from_framed_bytes(&bytes).unwrap(), message);
+    }
+
+    #[test]
+    fn rejects_unframed_bytes() {
+        let bytes = TestMsg::V1(123).to_bytes().unwrap();
+        let error = TestMsg::<i32>::from_framed_bytes(&bytes).unwrap_err();
+
+        assert!(error.is_invalid_frame());
+    }
+
+    #[test]
+    fn detects_corruption() {
+        let mut bytes = TestMsg::V1(123).to_framed_bytes().unwrap();
+        let len = bytes.len();
+        bytes[len - CHECKSUM_LEN - 1] ^= 1;
+
+        let error = TestMsg::<i32>::from_framed_bytes(&bytes).unwrap_err();
+        assert!(error.is_checksum_mismatch());
+    }
+
+    #[test]
+    fn detects_type_mismatch() {
+        let bytes = TestMsg::V1(123u8).to_framed_bytes().unwrap();
+        let error = TestMsg::<i32>::from_framed_bytes(&bytes).unwrap_err();
+
+        assert_eq!(
+            error.kind(),
+            &ErrorKind::TypeMismatch {
+                expected: "test/TestMsg<i32>",
+                found: FrameTypeId::new("test/TestMsg<u8>"),
+            }
+        );
+    }
+
+    #[test]
+    fn frame_type_id_is_truncated_at_a_char_boundary() {
+        let long = "é".repeat(FrameTypeId::MAX_LEN);
+        let found = FrameTypeId::new(&long);
+
+        assert_eq!(found.as_str(), "é".repeat(FrameTypeId::MAX_LEN / 2));
+        assert_eq!(FrameTypeId::new("short").as_str(), "short");
+    }
+}
@@ -0,0 +1,279 @@
+use serde::{
+    de::DeserializeOwned, forward_to_deserialize_any, Deserialize, Deserializer, Serialize,
+};
+use serde_json::{Map, Value};
+
+use crate::{Error, ErrorKind, WireMessage};
+
+/// [`WireMessage`]s with a canonical, versioned JSON representation
+///
+/// The JSON form of a message is an object containing its version and the contents of the
+/// variant for that version:
+/// ```json
+/// { "version": 2, "data": ... }
+/// ```
+///
+/// Like the binary form, reading JSON with [`VersionedJson::from_json`] upgrades the message to
+/// [`WireMessage::MAX_VERSION`], so JSON APIs get the same forward-compatibility as the binary
+/// format.
+///
+/// This trait is implemented for every [`WireMessage`] that derives [`Serialize`] and
+/// [`Deserialize`] with serde's default (externally tagged) enum representation. Version `n` must
+/// be the `n`th variant, which is always the case for impls generated by
+/// [`wire_message`][crate::wire_message]
+///
+/// ```rust
+/// # use serde::{Deserialize, Serialize};
+/// # use wire_message::{wire_message, Error, VersionedJson, WireMessage};
+/// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+/// #[wire_message]
+/// enum Message {
+///     #[upgrade_with = v1_to_v2]
+///     V1(u32),
+///     V2(u64),
+/// }
+///
+/// fn v1_to_v2(v1: u32, _ctx: &mut ()) -> Result<u64, Error> {
+///     Ok(v1.into())
+/// }
+///
+/// assert_eq!(
+///     Message::V1(123).to_json().unwrap(),
+///     r#"{"version":1,"data":123}"#,
+/// );
+///
+/// let message = Message::from_json(r#"{"version":1,"data":123}"#, &mut ()).unwrap();
+/// assert_eq!(message, Message::V2(123));
+/// ```
+pub trait VersionedJson: WireMessage + Serialize + DeserializeOwned {
+    /// Convert this message to its versioned JSON representation
+    fn to_json_value(&self) -> Result<Value, Error> {
+        serde_json::to_value(envelope(self)?).map_err(serialize_error)
+    }
+
+    /// Serialize this message as a versioned JSON string
+    fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string(&envelope(self)?).map_err(serialize_error)
+    }
+
+    /// Read a message from its versioned JSON representation, and upgrade it to
+    /// [`WireMessage::MAX_VERSION`]
+    fn from_json_value(value: Value, ctx: &mut Self::Ctx) -> Result<Self, Error<Self::Err>> {
+        let Envelope { version, data } =
+            serde_json::from_value(value).map_err(deserialize_error)?;
+
+        if version == 0 || version > Self::MAX_VERSION {
+            return Err(Self::unsupported_version_error(version));
+        }
+
+        let message = <Self as Deserialize>::deserialize(VersionDeserializer { version, data })
+            .map_err(deserialize_error)?;
+
+        message.upgrade(ctx)
+    }
+
+    /// Deserialize a message from a versioned JSON string, and upgrade it to
+    /// [`WireMessage::MAX_VERSION`]
+    fn from_json(json: &str, ctx: &mut Self::Ctx) -> Result<Self, Error<Self::Err>> {
+        let value = serde_json::from_str(json).map_err(deserialize_error)?;
+        Self::from_json_value(value, ctx)
+    }
+}
+
+impl<T> VersionedJson for T where T: WireMessage + Serialize + DeserializeOwned {}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Envelope {
+    version: u64,
+    data: Value,
+}
+
+/// Replace the variant name in the externally tagged form of `message` with its version
+fn envelope<M: VersionedJson>(message: &M) -> Result<Envelope, Error> {
+    let not_tagged = || {
+        serialize_error(serde::ser::Error::custom(
+            "versioned JSON requires an externally tagged enum",
+        ))
+    };
+
+    let Value::Object(variant) = serde_json::to_value(message).map_err(serialize_error)? else {
+        return Err(not_tagged());
+    };
+
+    let mut values = variant.into_iter();
+    let (Some((_, data)), None) = (values.next(), values.next()) else {
+        return Err(not_tagged());
+    };
+
+    Ok(Envelope {
+        version: message.version(),
+        data,
+    })
+}
+
+fn serialize_error(e: serde_json::Error) -> Error {
+    Error {
+        kind: ErrorKind::Serialize,
+        backtrace: std::backtrace::Backtrace::capture(),
+        source: Some(e.into()),
+    }
+}
+
+fn deserialize_error<T>(e: serde_json::Error) -> Error<T> {
+    Error {
+        kind: ErrorKind::Deserialize,
+        backtrace: std::backtrace::Backtrace::capture(),
+        source: Some(e.into()),
+    }
+}
+
+/// Deserializes the variant for `version` from `data`
+///
+/// serde only tells us the names of an enum's variants when deserializing it, so this maps the
+/// version to the variant name, and then deserializes the usual externally tagged form
+struct VersionDeserializer {
+    version: u64,
+    data: Value,
+}
+
+impl<'de> Deserializer<'de> for VersionDeserializer {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V>(self, _visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        Err(serde::de::Error::custom(
+            "versioned JSON can only be deserialized into an enum",
+        ))
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: serde::de::Visitor<'de>,
+    {
+        let variant = usize::try_from(self.version)
+            .ok()
+            .and_then(|version| variants.get(version.checked_sub(1)?))
+            .ok_or_else(|| {
+                serde::de::Error::custom(format!(
+                    "`{name}` has no variant for version {}",
+                    self.version
+                ))
+            })?;
+
+        let tagged = Value::Object(Map::from_iter([(variant.to_string(), self.data)]));
+        tagged.deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option
+        unit unit_struct newtype_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[derive(
+        borsh::BorshSerialize,
+        borsh::BorshDeserialize,
+        strum_macros::EnumCount,
+        Serialize,
+        Deserialize,
+        Debug,
+        PartialEq,
+    )]
+    enum TestMsg {
+        V1(u32),
+        V2 { value: u64, label: String },
+    }
+
+    impl WireMessage for TestMsg {
+        type Ctx = ();
+        type Err = core::convert::Infallible;
+
+        fn version(&self) -> u64 {
+            match self {
+                Self::V1(_) => 1,
+                Self::V2 { .. } => 2,
+            }
+        }
+
+        fn upgrade_once(self, _ctx: &mut Self::Ctx) -> Result<Self, Error> {
+            match self {
+                Self::V1(value) => Ok(Self::V2 {
+                    value: value.into(),
+                    label: String::new(),
+                }),
+                Self::V2 { .. } => Err(Self::max_version_error()),
+            }
+        }
+    }
+
+    #[test]
+    fn json_shape() {
+        assert_eq!(
+            TestMsg::V1(1).to_json_value().unwrap(),
+            json!({ "version": 1, "data": 1 })
+        );
+
+        let message = TestMsg::V2 {
+            value: 2,
+            label: "two".to_string(),
+        };
+
+        assert_eq!(
+            message.to_json_value().unwrap(),
+            json!({ "version": 2, "data": { "value": 2, "label": "two" } })
+        );
+    }
+
+    #[test]
+    fn from_json_upgrades() {
+        let json = TestMsg::V1(1).to_json().unwrap();
+
+        assert_eq!(
+            TestMsg::from_json(&json, &mut ()).unwrap(),
+            TestMsg::V2 {
+                value: 1,
+                label: String::new()
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_versions() {
+        for version in [0, 3] {
+            let value = json!({ "version": version, "data": 1 });
+            let error = TestMsg::from_json_value(value, &mut ()).unwrap_err();
+
+            assert!(error.is_unsupported_version());
+        }
+    }
+
+    #[test]
+    fn rejects_data_for_the_wrong_version() {
+        let value = json!({ "version": 2, "data": 1 });
+        let error = TestMsg::from_json_value(value, &mut ()).unwrap_err();
+
+        assert!(error.is_deserialize());
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        let value = json!({ "version": 1, "data": 1, "extra": true });
+        let error = TestMsg::from_json_value(value, &mut ()).unwrap_err();
+
+        assert!(error.is_deserialize());
+    }
+}
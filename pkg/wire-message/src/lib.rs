@@ -8,11 +8,151 @@ pub use borsh;
 pub use static_assertions;
 pub use strum_macros;
 
+#[cfg(feature = "codec")]
+pub use codec::{WireMessageCodec, DEFAULT_MAX_MESSAGE_SIZE};
 pub use error::{Error, ErrorKind};
+pub use frame::{FrameTypeId, Framed, FRAME_MAGIC};
+#[cfg(feature = "json")]
+pub use json::VersionedJson;
 
 /// Add required supertrait impls to a [`WireMessage`] implementer
+///
+/// This also generates the [`WireMessage`] impl:
+///  - `version` is the 1-based position of the variant
+///  - `upgrade_once` calls the `upgrade_with` function of the current variant, which takes the
+///    field of that variant and the context, and returns the field of the next variant
+///  - `Ctx` and `Err` default to `()` and [`Infallible`][core::convert::Infallible]. Upgrade
+///    functions return `Result<_, Error<Err>>`, and can report a custom error with
+///    [`Error::upgrade`]
+///
+/// ```rust
+/// # use wire_message::{wire_message, Error, WireMessage};
+/// #[wire_message]
+/// enum Message {
+///     #[upgrade_with = v1_to_v2]
+///     V1(u32),
+///     V2(u64),
+/// }
+///
+/// fn v1_to_v2(v1: u32, _ctx: &mut ()) -> Result<u64, Error> {
+///     Ok(v1.into())
+/// }
+///
+/// let message = Message::V1(1).upgrade(&mut ()).unwrap();
+/// assert_eq!(message.version(), 2);
+/// ```
+///
+/// To write the [`WireMessage`] impl by hand, use `#[wire_message(manual)]`, which only adds the
+/// supertrait impls:
+///
+/// ```rust
+/// # use wire_message::{wire_message, Error, WireMessage};
+/// #[wire_message(manual)]
+/// enum Message {
+///     V1(u32),
+/// }
+///
+/// impl WireMessage for Message {
+///     type Ctx = ();
+///     type Err = core::convert::Infallible;
+///
+///     fn version(&self) -> u64 {
+///         1
+///     }
+///
+///     fn upgrade_once(self, _ctx: &mut ()) -> Result<Self, Error> {
+///         Err(Self::max_version_error())
+///     }
+/// }
+/// ```
+///
+/// Variants can also have a `#[downgrade_with = ...]` function, which takes the field of that
+/// variant and the context, and returns the field of the previous variant. If any variant has one,
+/// `downgrade_once` is generated, and variants without one are treated as lossy to downgrade (see
+/// [`WireMessage::downgrade_once`]):
+///
+/// ```rust
+/// # use wire_message::{wire_message, Error, WireMessage};
+/// #[derive(Debug)]
+/// #[wire_message]
+/// enum Message {
+///     #[upgrade_with = v1_to_v2]
+///     V1(u32),
+///     #[downgrade_with = v2_to_v1]
+///     V2(u64),
+/// }
+///
+/// fn v1_to_v2(v1: u32, _ctx: &mut ()) -> Result<u64, Error> {
+///     Ok(v1.into())
+/// }
+///
+/// fn v2_to_v1(v2: u64, _ctx: &mut ()) -> Result<u32, Error> {
+///     u32::try_from(v2).map_err(|_| Message::V2(v2).lossy_downgrade_error())
+/// }
+///
+/// let bytes = Message::V2(1).to_bytes_at_version(1, &mut ()).unwrap();
+/// assert!(matches!(Message::from_bytes(&bytes).unwrap(), Message::V1(1)));
+///
+/// let error = Message::V2(u64::MAX).to_version(1, &mut ()).unwrap_err();
+/// assert!(error.is_lossy_downgrade());
+/// ```
+///
+/// The `type_id = "..."` argument implements [`Framed`] with the given stable type identifier.
+///
+/// Every variant except the last must have an `upgrade_with` function:
+/// ```rust,compile_fail
+/// # use wire_message::{wire_message, Error, WireMessage};
+/// #[wire_message]
+/// enum Message {
+///     #[upgrade_with = v1_to_v2]
+///     V1(u32),
+///     V2(u64),  // missing `upgrade_with`
+///     V3(u64),
+/// }
+/// # fn v1_to_v2(v1: u32, _ctx: &mut ()) -> Result<u64, Error> { Ok(v1.into()) }
+/// ```
+/// And the last variant (the max version) must not have one:
+/// ```rust,compile_fail
+/// # use wire_message::{wire_message, Error, WireMessage};
+/// #[wire_message]
+/// enum Message {
+///     #[upgrade_with = v1_to_v2]
+///     V1(u32),
+///     #[upgrade_with = v1_to_v2]
+///     V2(u32),
+/// }
+/// # fn v1_to_v2(v1: u32, _ctx: &mut ()) -> Result<u32, Error> { Ok(v1) }
+/// ```
+/// Similarly, the first variant (the min version) can't have a `downgrade_with` function:
+/// ```rust,compile_fail
+/// # use wire_message::{wire_message, Error, WireMessage};
+/// #[wire_message]
+/// enum Message {
+///     #[upgrade_with = v1_to_v2]
+///     #[downgrade_with = v1_to_v2]
+///     V1(u32),
+///     V2(u32),
+/// }
+/// # fn v1_to_v2(v1: u32, _ctx: &mut ()) -> Result<u32, Error> { Ok(v1) }
+/// ```
+/// With `manual`, the upgrade and downgrade functions would be ignored, so they aren't allowed:
+/// ```rust,compile_fail
+/// # use wire_message::{wire_message, Error, WireMessage};
+/// #[wire_message(manual)]
+/// enum Message {
+///     #[upgrade_with = v1_to_v2]
+///     V1(u32),
+///     V2(u64),
+/// }
+/// # fn v1_to_v2(v1: u32, _ctx: &mut ()) -> Result<u64, Error> { Ok(v1.into()) }
+/// ```
 pub use wire_message_macro::wire_message;
+#[cfg(feature = "codec")]
+mod codec;
 mod error;
+mod frame;
+#[cfg(feature = "json")]
+mod json;
 
 #[cfg(feature = "test-api")]
 pub mod test_api;
@@ -22,6 +162,7 @@ pub mod test_api;
 /// The easiest way to implement this trait is to add the `#[wire_message]` attribute, which will:
 ///  - implement all the required supertraits
 ///  - guarantee that you are implementing it on an enum
+///  - optionally generate `version` and `upgrade_once` (see [`wire_message`] for details)
 pub trait WireMessage:
     Sized + BorshSerialize + BorshDeserialize + EnumCount + Send + Sync + 'static
 {
@@ -30,6 +171,9 @@ pub trait WireMessage:
 
     /// The type of custom errors produced by `upgrade` functions
     ///
+    /// These are reported as [`ErrorKind::Upgrade`], and can be constructed with
+    /// [`Error::upgrade`].
+    ///
     /// If your upgrade cannot fail (other than upgrading past the maximum version), consider using
     /// [`core::convert::Infallible`] to mark this case as impossible (this would be the default if
     /// Rust supported defaults on associated types)
@@ -41,12 +185,12 @@ pub trait WireMessage:
     /// The current version of this value
     fn version(&self) -> u64;
 
-    /// Upgrade this message to the next highest version, or return `None` if it is already at the
-    /// max version
-    fn upgrade_once(self, ctx: &mut Self::Ctx) -> Result<Self, Error>;
+    /// Upgrade this message to the next highest version, or return
+    /// [`Self::max_version_error`] if it is already at the max version
+    fn upgrade_once(self, ctx: &mut Self::Ctx) -> Result<Self, Error<Self::Err>>;
 
     /// Upgrade this message until it is at [`Self::MAX_VERSION`]
-    fn upgrade(mut self, ctx: &mut Self::Ctx) -> Result<Self, Error> {
+    fn upgrade(mut self, ctx: &mut Self::Ctx) -> Result<Self, Error<Self::Err>> {
         while self.version() < Self::MAX_VERSION {
             self = self.upgrade_once(ctx)?;
         }
@@ -54,6 +198,59 @@ pub trait WireMessage:
         Ok(self)
     }
 
+    /// Downgrade this message to the next lowest version
+    ///
+    /// This is used during rolling upgrades, to talk to peers that only understand an earlier
+    /// version. If the downgrade would lose information, this should return
+    /// [`Self::lossy_downgrade_error`]. The default implementation assumes every downgrade is
+    /// lossy
+    fn downgrade_once(self, _ctx: &mut Self::Ctx) -> Result<Self, Error<Self::Err>> {
+        Err(self.lossy_downgrade_error())
+    }
+
+    /// Upgrade or downgrade this message until it is at `version`
+    ///
+    /// Returns an [`ErrorKind::UnsupportedVersion`] error if `version` is not in the range
+    /// `1..=Self::MAX_VERSION`
+    fn to_version(mut self, version: u64, ctx: &mut Self::Ctx) -> Result<Self, Error<Self::Err>> {
+        if version == 0 || version > Self::MAX_VERSION {
+            return Err(Self::unsupported_version_error(version));
+        }
+
+        while self.version() < version {
+            self = self.upgrade_once(ctx)?;
+        }
+
+        while self.version() > version {
+            self = self.downgrade_once(ctx)?;
+        }
+
+        Ok(self)
+    }
+
+    /// Convert this message to `version` (see [`WireMessage::to_version`]) and serialize it
+    fn to_bytes_at_version(
+        self,
+        version: u64,
+        ctx: &mut Self::Ctx,
+    ) -> Result<Vec<u8>, Error<Self::Err>> {
+        self.to_version(version, ctx)?
+            .to_bytes()
+            .map_err(Error::cast)
+    }
+
+    /// The highest version understood by both this node and a peer whose max version is
+    /// `peer_max_version`
+    ///
+    /// Messages sent to the peer should be converted to this version with
+    /// [`WireMessage::to_bytes_at_version`]
+    fn negotiate_version(peer_max_version: u64) -> Result<u64, Error> {
+        match peer_max_version.min(Self::MAX_VERSION) {
+            0 => Err(Self::unsupported_version_error(peer_max_version)),
+            version => Ok(version),
+        }
+    }
+
     /// Deserialize an instance of `Self` from bytes
     fn from_bytes(mut bytes: &[u8]) -> Result<Self, Error> {
         #[allow(clippy::disallowed_methods)]
@@ -96,7 +293,7 @@ pub trait WireMessage:
 
     /// Construct an [`Error`] representing the case where you are trying to upgrade the maximum
     /// version of a message type
-    fn max_version_error() -> Error {
+    fn max_version_error<T>() -> Error<T> {
         Error {
             kind: ErrorKind::MaxVersion {
                 version: Self::MAX_VERSION,
@@ -105,4 +302,21 @@ pub trait WireMessage:
             source: None,
         }
     }
+
+    /// Construct an [`Error`] representing the case where downgrading this message would lose
+    /// information (or where it is already at the minimum version)
+    fn lossy_downgrade_error<T>(&self) -> Error<T> {
+        Error::from_kind(ErrorKind::LossyDowngrade {
+            version: self.version(),
+        })
+    }
+
+    /// Construct an [`Error`] representing the case where `version` is not a version of this
+    /// message type
+    fn unsupported_version_error<T>(version: u64) -> Error<T> {
+        Error::from_kind(ErrorKind::UnsupportedVersion {
+            version,
+            max_version: Self::MAX_VERSION,
+        })
+    }
 }
@@ -0,0 +1,362 @@
+use std::{
+    collections::BTreeSet,
+    fmt::Debug,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::WireMessage;
+
+/// Set this environment variable to write missing fixture files when running a [`Golden`] check
+pub const RECORD_ENV_VAR: &str = "WIRE_MESSAGE_RECORD";
+
+/// The file extension of fixture files
+const FIXTURE_EXTENSION: &str = "bin";
+
+/// A golden-file compatibility check for a [`WireMessage`] type
+///
+/// Each sample is a named value at some version of the message, which is stored in the fixture
+/// directory as `<name>.v<version>.bin`. Once committed, a fixture must never change, so the check
+/// asserts that:
+///  - every version from 1 to [`WireMessage::MAX_VERSION`] has at least one sample
+///  - every fixture in the directory belongs to a sample (so old samples can't be silently
+///    removed)
+///  - every fixture deserializes to its sample, and serializing the sample gives the same bytes
+///  - every fixture upgrades to the expected value at the max version
+///
+/// When adding a new version, add samples of it and run the test with [`RECORD_ENV_VAR`] set to
+/// write the new fixtures, then commit them. Fixtures are only written when this variable is set,
+/// so a missing fixture fails in CI, and existing fixtures are never overwritten, so a changed
+/// fixture fails even when recording.
+///
+/// ```rust,no_run
+/// # use wire_message::{wire_message, Error, WireMessage, test_api::Golden};
+/// #[derive(Debug, PartialEq)]
+/// #[wire_message]
+/// enum Message {
+///     #[upgrade_with = v1_to_v2]
+///     V1(u32),
+///     V2(u64),
+/// }
+///
+/// fn v1_to_v2(v1: u32, _ctx: &mut ()) -> Result<u64, Error> {
+///     Ok(v1.into())
+/// }
+///
+/// Golden::new(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/message"))
+///     .sample_upgrading_to("small", Message::V1(1), Message::V2(1))
+///     .sample("large", Message::V2(u64::MAX))
+///     .check(&mut ());
+/// ```
+#[derive(Debug)]
+pub struct Golden<M> {
+    dir: PathBuf,
+    samples: Vec<Sample<M>>,
+}
+
+#[derive(Debug)]
+struct Sample<M> {
+    name: String,
+    message: M,
+    /// The value at the max version, or `None` to upgrade `message`
+    expected: Option<M>,
+}
+
+impl<M> Golden<M>
+where
+    M: WireMessage + Debug + PartialEq,
+    M::Err: Debug,
+{
+    /// Create a check with the fixtures in `dir`
+    ///
+    /// This is usually a path inside the crate, e.g.
+    /// `concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/my_message")`
+    #[must_use]
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            samples: Vec::new(),
+        }
+    }
+
+    /// Add a sample, whose expected value at the max version is found by upgrading it
+    ///
+    /// This checks that old bytes still produce the same value as the current upgrade logic, but
+    /// not that the upgrade logic itself is unchanged. Use [`Golden::sample_upgrading_to`] to pin
+    /// the upgraded value too
+    #[must_use]
+    pub fn sample(self, name: &str, message: M) -> Self {
+        self.push(name, message, None)
+    }
+
+    /// Add a sample, which must upgrade to `expected`
+    #[must_use]
+    pub fn sample_upgrading_to(self, name: &str, message: M, expected: M) -> Self {
+        self.push(name, message, Some(expected))
+    }
+
+    fn push(mut self, name: &str, message: M, expected: Option<M>) -> Self {
+        assert!(
+            !name.is_empty() && !name.contains(['/', '\\', '.']),
+            "sample names must be non-empty and can't contain `/`, `\\` or `.`, but found {name:?}"
+        );
+
+        self.samples.push(Sample {
+            name: name.to_string(),
+            message,
+            expected,
+        });
+
+        self
+    }
+
+    /// Run the check, using `ctx` to upgrade each fixture
+    ///
+    /// If [`RECORD_ENV_VAR`] is set, the fixtures that don't exist yet are written before they
+    /// are checked
+    ///
+    /// # Panics
+    ///
+    /// Panics with a description of the problem if any of the checks fail
+    pub fn check(self, ctx: &mut M::Ctx) {
+        let record = std::env::var_os(RECORD_ENV_VAR).is_some();
+        self.run(ctx, record);
+    }
+
+    fn run(self, ctx: &mut M::Ctx, record: bool) {
+        if record {
+            self.record();
+        }
+
+        self.check_versions_covered();
+        self.check_no_orphaned_fixtures();
+
+        for sample in self.samples {
+            let path = fixture_path(&self.dir, &sample.name, sample.message.version());
+            let bytes = fs::read(&path).unwrap_or_else(|e| {
+                panic!(
+                    "couldn't read fixture {}: {e}\n\
+                     run the test with {RECORD_ENV_VAR}=1 to record it, then commit it",
+                    path.display()
+                )
+            });
+
+            let decoded = M::from_bytes(&bytes).unwrap_or_else(|e| {
+                panic!("fixture {} no longer deserializes: {e:?}", path.display())
+            });
+
+            assert_eq!(
+                decoded,
+                sample.message,
+                "fixture {} deserialized to a different value",
+                path.display()
+            );
+
+            assert_eq!(
+                sample.message.to_bytes().unwrap(),
+                bytes,
+                "sample {:?} no longer serializes to the bytes in {}",
+                sample.name,
+                path.display()
+            );
+
+            let upgraded = decoded
+                .upgrade(ctx)
+                .unwrap_or_else(|e| panic!("fixture {} failed to upgrade: {e:?}", path.display()));
+
+            let expected = match sample.expected {
+                Some(expected) => expected,
+                None => sample.message.upgrade(ctx).unwrap(),
+            };
+
+            assert_eq!(
+                upgraded,
+                expected,
+                "fixture {} upgraded to an unexpected value",
+                path.display()
+            );
+        }
+    }
+
+    /// Write the fixtures that don't exist yet, leaving existing ones to be checked
+    fn record(&self) {
+        fs::create_dir_all(&self.dir).unwrap();
+
+        for sample in &self.samples {
+            let path = fixture_path(&self.dir, &sample.name, sample.message.version());
+            if path.exists() {
+                continue;
+            }
+
+            fs::write(&path, sample.message.to_bytes().unwrap()).unwrap();
+        }
+    }
+
+    fn check_versions_covered(&self) {
+        let versions: BTreeSet<u64> = self.samples.iter().map(|s| s.message.version()).collect();
+
+        for version in 1..=M::MAX_VERSION {
+            assert!(
+                versions.contains(&version),
+                "there are no samples of version {version}, so it isn't covered by any fixture"
+            );
+        }
+    }
+
+    fn check_no_orphaned_fixtures(&self) {
+        let expected: BTreeSet<PathBuf> = self
+            .samples
+            .iter()
+            .map(|s| fixture_path(&self.dir, &s.name, s.message.version()))
+            .collect();
+
+        let entries = fs::read_dir(&self.dir).unwrap_or_else(|e| {
+            panic!(
+                "couldn't read fixture directory {}: {e}\n\
+                 run the test with {RECORD_ENV_VAR}=1 to record the fixtures, then commit them",
+                self.dir.display()
+            )
+        });
+
+        for entry in entries {
+            let path = entry.unwrap().path();
+
+            if path.extension() == Some(FIXTURE_EXTENSION.as_ref()) {
+                assert!(
+                    expected.contains(&path),
+                    "fixture {} has no sample, but fixtures must never be removed",
+                    path.display()
+                );
+            }
+        }
+    }
+}
+
+fn fixture_path(dir: &Path, name: &str, version: u64) -> PathBuf {
+    dir.join(format!("{name}.v{version}.{FIXTURE_EXTENSION}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(
+        borsh::BorshSerialize, borsh::BorshDeserialize, strum_macros::EnumCount, Debug, PartialEq,
+    )]
+    enum TestMsg {
+        V1(u8),
+        V2(u16),
+    }
+
+    impl WireMessage for TestMsg {
+        type Ctx = ();
+        type Err = core::convert::Infallible;
+
+        fn version(&self) -> u64 {
+            match self {
+                Self::V1(_) => 1,
+                Self::V2(_) => 2,
+            }
+        }
+
+        fn upgrade_once(self, _ctx: &mut Self::Ctx) -> Result<Self, crate::Error> {
+            match self {
+                Self::V1(value) => Ok(Self::V2(value.into())),
+                Self::V2(_) => Err(Self::max_version_error()),
+            }
+        }
+    }
+
+    /// A fresh fixture directory for a test
+    fn fixture_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("wire-message-golden-{}", std::process::id()))
+            .join(name);
+
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_fixtures(dir: &Path) {
+        fs::write(dir.join("one.v1.bin"), [0, 1]).unwrap();
+        fs::write(dir.join("two.v2.bin"), [1, 2, 0]).unwrap();
+    }
+
+    fn golden(dir: &Path) -> Golden<TestMsg> {
+        Golden::new(dir)
+            .sample_upgrading_to("one", TestMsg::V1(1), TestMsg::V2(1))
+            .sample("two", TestMsg::V2(2))
+    }
+
+    #[test]
+    fn passes_with_matching_fixtures() {
+        let dir = fixture_dir("passes");
+        write_fixtures(&dir);
+
+        golden(&dir).check(&mut ());
+    }
+
+    #[test]
+    #[should_panic = "deserialized to a different value"]
+    fn detects_changed_fixture() {
+        let dir = fixture_dir("changed");
+        write_fixtures(&dir);
+        fs::write(dir.join("one.v1.bin"), [0, 2]).unwrap();
+
+        golden(&dir).check(&mut ());
+    }
+
+    #[test]
+    fn records_missing_fixtures() {
+        let dir = fixture_dir("record");
+        fs::write(dir.join("one.v1.bin"), [0, 1]).unwrap();
+
+        golden(&dir).run(&mut (), true);
+
+        assert_eq!(fs::read(dir.join("two.v2.bin")).unwrap(), [1, 2, 0]);
+    }
+
+    #[test]
+    #[should_panic = "deserialized to a different value"]
+    fn recording_keeps_changed_fixture() {
+        let dir = fixture_dir("record_changed");
+        write_fixtures(&dir);
+        fs::write(dir.join("one.v1.bin"), [0, 2]).unwrap();
+
+        golden(&dir).run(&mut (), true);
+    }
+
+    #[test]
+    #[should_panic = "upgraded to an unexpected value"]
+    fn detects_changed_upgrade() {
+        let dir = fixture_dir("upgrade");
+        write_fixtures(&dir);
+
+        Golden::new(&dir)
+            .sample_upgrading_to("one", TestMsg::V1(1), TestMsg::V2(2))
+            .sample("two", TestMsg::V2(2))
+            .check(&mut ());
+    }
+
+    #[test]
+    #[should_panic = "no samples of version 2"]
+    fn requires_every_version() {
+        let dir = fixture_dir("versions");
+        write_fixtures(&dir);
+
+        Golden::new(&dir)
+            .sample("one", TestMsg::V1(1))
+            .check(&mut ());
+    }
+
+    #[test]
+    #[should_panic = "has no sample"]
+    fn detects_orphaned_fixture() {
+        let dir = fixture_dir("orphaned");
+        write_fixtures(&dir);
+        fs::write(dir.join("three.v1.bin"), [0, 3]).unwrap();
+
+        golden(&dir).check(&mut ());
+    }
+}
@@ -2,6 +2,10 @@ use borsh::{BorshDeserialize, BorshSerialize};
 
 use crate::WireMessage;
 
+mod golden;
+
+pub use golden::{Golden, RECORD_ENV_VAR};
+
 /// Dummy message type for use in testing
 #[derive(
     ::borsh::BorshSerialize,
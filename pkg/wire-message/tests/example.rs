@@ -2,7 +2,7 @@
 use borsh::{BorshDeserialize, BorshSerialize};
 use wire_message::{wire_message, Error, WireMessage};
 
-#[wire_message]
+#[wire_message(manual)]
 enum ExampleMessage {
     V1(V1),
     V2(V2),
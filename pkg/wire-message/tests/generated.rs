@@ -0,0 +1,250 @@
+use std::{collections::HashMap, sync::Arc};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use wire_message::{wire_message, Error, WireMessage};
+
+#[derive(Debug, PartialEq)]
+#[wire_message]
+enum Message {
+    #[upgrade_with = v1_to_v2]
+    V1(V1),
+    #[upgrade_with = v2_to_v3]
+    V2(V2),
+    V3(V3),
+}
+
+#[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+struct V1 {
+    payload: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+struct V2 {
+    payload: Vec<u8>,
+    metadata: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+struct V3 {
+    metadata: Vec<u8>,
+}
+
+fn v1_to_v2(V1 { payload }: V1, _ctx: &mut ()) -> Result<V2, Error> {
+    Ok(V2 {
+        payload,
+        metadata: vec![],
+    })
+}
+
+fn v2_to_v3(V2 { metadata, .. }: V2, _ctx: &mut ()) -> Result<V3, Error> {
+    Ok(V3 { metadata })
+}
+
+#[test]
+fn version_from_position() {
+    assert_eq!(Message::V1(V1 { payload: vec![] }).version(), 1);
+    assert_eq!(Message::V3(V3 { metadata: vec![] }).version(), 3);
+    assert_eq!(Message::MAX_VERSION, 3);
+}
+
+#[derive(Debug, PartialEq)]
+#[wire_message]
+enum SingleVersion {
+    V1(u32),
+}
+
+#[test]
+fn single_version_needs_no_arguments() {
+    let message = SingleVersion::V1(1);
+    assert_eq!(message.version(), 1);
+    assert_eq!(SingleVersion::MAX_VERSION, 1);
+
+    let bytes = message.to_bytes().unwrap();
+    assert_eq!(SingleVersion::from_bytes(&bytes).unwrap(), message);
+}
+
+#[test]
+fn upgrade_with_generated_impl() {
+    let message = Message::V1(V1 {
+        payload: vec![1, 2, 3],
+    });
+
+    let v2 = message.upgrade_once(&mut ()).unwrap();
+    assert_eq!(
+        v2,
+        Message::V2(V2 {
+            payload: vec![1, 2, 3],
+            metadata: vec![]
+        })
+    );
+
+    let v3 = v2.upgrade(&mut ()).unwrap();
+    assert_eq!(v3, Message::V3(V3 { metadata: vec![] }));
+
+    let error = v3.upgrade_once(&mut ()).unwrap_err();
+    assert!(error.is_max_version());
+}
+
+#[wire_message(ctx = u32)]
+enum WithCtx {
+    #[upgrade_with = add_ctx]
+    V1(u32),
+    V2(u32),
+}
+
+fn add_ctx(value: u32, ctx: &mut u32) -> Result<u32, Error> {
+    *ctx += 1;
+    Ok(value + *ctx)
+}
+
+#[test]
+fn upgrade_uses_ctx() {
+    let mut ctx = 10;
+    let message = WithCtx::V1(1).upgrade(&mut ctx).unwrap();
+
+    assert_eq!(ctx, 11);
+    assert!(matches!(message, WithCtx::V2(12)));
+}
+
+#[wire_message]
+enum Generic<T: Clone> {
+    #[upgrade_with = generic_v1_to_v2]
+    V1(Arc<T>),
+    V2(Vec<T>),
+}
+
+fn generic_v1_to_v2<T: Clone>(value: Arc<T>, _ctx: &mut ()) -> Result<Vec<T>, Error> {
+    Ok(vec![T::clone(&value)])
+}
+
+#[test]
+fn generic_enum() {
+    let message = Generic::V1(Arc::new(1u8)).upgrade(&mut ()).unwrap();
+
+    assert_eq!(message.version(), 2);
+    assert!(matches!(message, Generic::V2(v) if v == [1]));
+}
+
+#[derive(Debug)]
+#[wire_message]
+enum Downgradable {
+    #[upgrade_with = widen]
+    V1(u32),
+    #[upgrade_with = widen_again]
+    #[downgrade_with = narrow]
+    V2(u64),
+    V3(u128),
+}
+
+fn widen(value: u32, _ctx: &mut ()) -> Result<u64, Error> {
+    Ok(value.into())
+}
+
+fn widen_again(value: u64, _ctx: &mut ()) -> Result<u128, Error> {
+    Ok(value.into())
+}
+
+fn narrow(value: u64, _ctx: &mut ()) -> Result<u32, Error> {
+    u32::try_from(value).map_err(|_| Downgradable::V2(value).lossy_downgrade_error())
+}
+
+#[test]
+fn to_version_downgrades() {
+    let message = Downgradable::V2(5).to_version(1, &mut ()).unwrap();
+    assert!(matches!(message, Downgradable::V1(5)));
+
+    let bytes = Downgradable::V2(5).to_bytes_at_version(1, &mut ()).unwrap();
+    assert!(matches!(
+        Downgradable::from_bytes(&bytes).unwrap(),
+        Downgradable::V1(5)
+    ));
+
+    let message = Downgradable::V1(5).to_version(3, &mut ()).unwrap();
+    assert!(matches!(message, Downgradable::V3(5)));
+}
+
+#[test]
+fn lossy_downgrade_is_an_error() {
+    // `V3` has no `downgrade_with`
+    let error = Downgradable::V3(5).to_version(2, &mut ()).unwrap_err();
+    assert!(error.is_lossy_downgrade());
+
+    // `narrow` rejects values that don't fit
+    let error = Downgradable::V2(u64::MAX)
+        .to_version(1, &mut ())
+        .unwrap_err();
+    assert!(error.is_lossy_downgrade());
+}
+
+#[test]
+fn to_version_rejects_unknown_versions() {
+    assert!(Downgradable::V1(1)
+        .to_version(0, &mut ())
+        .unwrap_err()
+        .is_unsupported_version());
+    assert!(Downgradable::V1(1)
+        .to_version(4, &mut ())
+        .unwrap_err()
+        .is_unsupported_version());
+}
+
+#[test]
+fn negotiate_version() {
+    assert_eq!(Downgradable::negotiate_version(2).unwrap(), 2);
+    assert_eq!(Downgradable::negotiate_version(10).unwrap(), 3);
+    assert!(Downgradable::negotiate_version(0).is_err());
+}
+
+#[derive(Debug, PartialEq)]
+struct MissingRow(u32);
+
+impl std::fmt::Display for MissingRow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "no row with id {}", self.0)
+    }
+}
+
+impl std::error::Error for MissingRow {}
+
+/// A "database" of names, looked up by id
+type Names = HashMap<u32, String>;
+
+#[derive(Debug)]
+#[wire_message(ctx = Names, err = MissingRow)]
+enum User {
+    #[upgrade_with = lookup_name]
+    V1(u32),
+    V2(String),
+}
+
+fn lookup_name(id: u32, names: &mut Names) -> Result<String, Error<MissingRow>> {
+    names
+        .get(&id)
+        .cloned()
+        .ok_or_else(|| Error::upgrade(MissingRow(id)))
+}
+
+#[test]
+fn custom_upgrade_error() {
+    let mut names = Names::from([(1, "alice".to_string())]);
+
+    let user = User::V1(1).upgrade(&mut names).unwrap();
+    assert!(matches!(user, User::V2(name) if name == "alice"));
+
+    let error = User::V1(2).upgrade(&mut names).unwrap_err();
+    assert!(error.is_upgrade());
+    assert_eq!(error.upgrade_error(), Some(&MissingRow(2)));
+
+    // the custom error is only in the source, so it isn't repeated in the error chain
+    assert_eq!(error.to_string(), "failed to upgrade");
+    let source = std::error::Error::source(&error).unwrap();
+    assert_eq!(source.to_string(), "no row with id 2");
+}
+
+#[test]
+fn io_errors_are_the_source() {
+    let error = Message::from_bytes(&[0xff]).unwrap_err();
+    let source = std::error::Error::source(&error).unwrap();
+
+    assert!(source.is::<std::io::Error>());
+}
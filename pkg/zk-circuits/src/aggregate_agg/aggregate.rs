@@ -6,7 +6,7 @@ use crate::{
     data::{AggregateAgg, ParameterSet},
     params::load_params,
     util::keygen_from_params,
-    CircuitKind,
+    CircuitKind, ErrorKind,
 };
 use halo2_base::halo2_proofs::{
     circuit::{Cell, Layouter, Value},
@@ -16,6 +16,18 @@ use halo2_base::halo2_proofs::{
 use itertools::Itertools;
 use smirk::Element;
 
+/// The root at `index` of the instances of `aggregate`
+fn root(aggregate: Option<&Snark>, index: usize) -> crate::Result<&Fr> {
+    aggregate
+        .and_then(|snark| snark.instances.first()?.get(index))
+        .ok_or_else(|| {
+            crate::Error::new(
+                ErrorKind::InvalidInput,
+                format!("no aggregate with a root at instance {index}"),
+            )
+        })
+}
+
 impl<const AGG_N: usize> AggregateAgg<AGG_N> {
     pub fn new(aggregates: [Snark; AGG_N]) -> Self {
         let snarks: Vec<&Snark> = Self::snarks(&aggregates);
@@ -90,34 +102,45 @@ impl<const AGG_N: usize> AggregateAgg<AGG_N> {
     }
 
     // TODO: we should use a typed system for extracting cells from the snark instances
-    pub fn public_inputs(&self) -> Vec<Fr> {
+    ///
+    /// Returns an [`ErrorKind::InvalidInput`] error if the aggregates don't have roots, see
+    /// [`AggregateAgg::new_root`]
+    pub fn public_inputs(&self) -> crate::Result<Vec<Fr>> {
         let mut instances = vec![];
 
         // Add verify instances (12)
         instances.extend(self.agg_instances.iter().copied().map(Fr::from));
 
         // Add old root (1)
-        instances.push(*self.old_root());
+        instances.push(*self.old_root()?);
 
         // Add new root (1)
-        instances.push(*self.new_root());
+        instances.push(*self.new_root()?);
 
         // UTXO values (recent root, mint/burn hash, mint/burn value) (= 3 per UTXO)
         instances.extend(self.utxo_values());
 
-        instances
+        Ok(instances)
     }
 
     pub fn agg_instances(&self) -> &Vec<Element> {
         &self.agg_instances
     }
 
-    pub fn old_root(&self) -> &Fr {
-        &self.aggregates[0].instances[0][12]
+    /// The old root of the first aggregate
+    ///
+    /// Returns an [`ErrorKind::InvalidInput`] error if there are no aggregates, or the first one
+    /// doesn't have an old root instance
+    pub fn old_root(&self) -> crate::Result<&Fr> {
+        root(self.aggregates.first(), 12)
     }
 
-    pub fn new_root(&self) -> &Fr {
-        &self.aggregates.iter().last().unwrap().instances[0][13]
+    /// The new root of the last aggregate
+    ///
+    /// Returns an [`ErrorKind::InvalidInput`] error if there are no aggregates, or the last one
+    /// doesn't have a new root instance
+    pub fn new_root(&self) -> crate::Result<&Fr> {
+        root(self.aggregates.last(), 13)
     }
 
     pub fn utxo_values(&self) -> Vec<Fr> {
@@ -128,15 +151,14 @@ impl<const AGG_N: usize> AggregateAgg<AGG_N> {
             .collect_vec()
     }
 
-    pub fn snark(&self, params: ParameterSet) -> Result<Snark, crate::Error> {
-        let pk = CircuitKind::AggAgg.pk();
+    pub fn snark(&self, params: ParameterSet) -> crate::Result<Snark> {
+        let pk = CircuitKind::AggAgg.pk()?;
         Snark::create(
             self.clone(),
-            vec![self.public_inputs()],
-            load_params(params),
+            vec![self.public_inputs()?],
+            load_params(params)?,
             pk,
         )
-        .map_err(crate::Error::err)
     }
 
     pub fn keygen(&self, params: ParameterSet) -> (ProvingKey<G1Affine>, VerifyingKey<G1Affine>) {
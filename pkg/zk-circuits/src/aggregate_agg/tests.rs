@@ -1,7 +1,10 @@
 use crate::{
     data::{AggregateAgg, ParameterSet},
     evm_verifier,
-    test::{agg_agg::create_or_load_agg_agg_utxo_snark, agg_utxo::create_or_load_agg_utxo_snarks},
+    test::{
+        agg_agg::{create_or_load_agg_agg_final_evm_proof, create_or_load_agg_agg_utxo_snark},
+        agg_utxo::create_or_load_agg_utxo_snarks,
+    },
 };
 use halo2_base::halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr};
 
@@ -13,8 +16,12 @@ fn test_aggregate_agg() {
 
     let aggregate_agg = AggregateAgg::<2>::new(utxo_aggs);
 
-    let prover =
-        MockProver::<Fr>::run(k, &aggregate_agg, vec![aggregate_agg.public_inputs()]).unwrap();
+    let prover = MockProver::<Fr>::run(
+        k,
+        &aggregate_agg,
+        vec![aggregate_agg.public_inputs().unwrap()],
+    )
+    .unwrap();
 
     prover.assert_satisfied();
 }
@@ -35,9 +42,41 @@ fn generate_verifier() {
     let yul_code = evm_verifier::generate_verifier(
         params_21,
         &pk,
-        vec![aggregate_agg_agg.public_inputs().len()],
-    );
+        vec![aggregate_agg_agg.public_inputs().unwrap().len()],
+    )
+    .unwrap();
 
     let expected_yul_code = expect_test::expect_file!["./aggregate_verifier.yul"];
     expected_yul_code.assert_eq(&yul_code);
 }
+
+#[test]
+fn deployed_verifier_verifies_proof() {
+    let params_21 = ParameterSet::TwentyOne;
+
+    let utxo_aggs = create_or_load_agg_utxo_snarks(params_21);
+    let aggregate_agg = create_or_load_agg_agg_utxo_snark(params_21, utxo_aggs);
+    let evm_proof = create_or_load_agg_agg_final_evm_proof(params_21, aggregate_agg)
+        .try_as_v_1()
+        .unwrap();
+
+    let gas = evm_verifier::DeployedVerifier::Aggregate
+        .verify(&[evm_proof.instances()], &evm_proof.proof)
+        .unwrap();
+    assert!(gas < 1_500_000, "aggregate verifier used {gas} gas");
+}
+
+#[test]
+fn no_aggregates_is_invalid_input() {
+    let aggregate_agg = AggregateAgg::<0> {
+        aggregates: [],
+        agg_instances: vec![],
+        proof: vec![],
+    };
+
+    let err = aggregate_agg.new_root().unwrap_err();
+    assert_eq!(err.kind(), crate::ErrorKind::InvalidInput);
+
+    let err = aggregate_agg.public_inputs().unwrap_err();
+    assert_eq!(err.kind(), crate::ErrorKind::InvalidInput);
+}
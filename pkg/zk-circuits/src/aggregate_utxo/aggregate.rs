@@ -10,10 +10,11 @@ use crate::{
         poseidon::PoseidonConfig,
         swap::CondSwapChip,
     },
+    constants::{UTXO_INPUTS, UTXO_OUTPUTS, UTXO_SHAPES},
     data::{Batch as BatchInsert, ParameterSet, Utxo},
     params::load_params,
     util::keygen_from_params,
-    CircuitKind,
+    CircuitKind, ErrorKind,
 };
 use halo2_base::halo2_proofs::{
     circuit::{Cell, Layouter, Value},
@@ -22,6 +23,22 @@ use halo2_base::halo2_proofs::{
 };
 use itertools::Itertools;
 
+/// The `(inputs, outputs)` shape of a UTXO `snark`, from its root, mint/burn hash and value
+/// instances followed by one instance per nullifier and commitment
+fn shape(snark: &Snark) -> crate::Result<(usize, usize)> {
+    let num_instance = snark.instances.first().map_or(0, Vec::len);
+
+    UTXO_SHAPES
+        .into_iter()
+        .find(|(inputs, outputs)| 3 + inputs + outputs == num_instance)
+        .ok_or_else(|| {
+            crate::Error::new(
+                ErrorKind::InvalidInput,
+                format!("no UTXO shape has {num_instance} instances"),
+            )
+        })
+}
+
 #[derive(Clone, Debug)]
 pub struct AggregateUtxo<const UTXO_N: usize, const MERKLE_D: usize, const LEAVES: usize> {
     /// UTXO to aggregate
@@ -40,18 +57,46 @@ pub struct AggregateUtxo<const UTXO_N: usize, const MERKLE_D: usize, const LEAVE
 impl<const UTXO_N: usize, const MERKLE_D: usize, const LEAVES: usize>
     AggregateUtxo<UTXO_N, MERKLE_D, LEAVES>
 {
-    pub fn new(utxo: [Snark; UTXO_N], insert: BatchInsert<LEAVES, MERKLE_D>) -> Self {
+    /// The UTXO can be of any shape in [`UTXO_SHAPES`], and their leaves are the first leaves of
+    /// `insert`.
+    ///
+    /// Returns an [`ErrorKind::InvalidInput`] error if a UTXO is not of one of those shapes, or
+    /// if the UTXO have more than `LEAVES` leaves
+    pub fn new(
+        utxo: [Snark; UTXO_N],
+        insert: BatchInsert<LEAVES, MERKLE_D>,
+    ) -> crate::Result<Self> {
+        let leaves = utxo
+            .iter()
+            .map(|snark| shape(snark).map(|(inputs, outputs)| inputs + outputs))
+            .sum::<crate::Result<usize>>()?;
+
+        if leaves > LEAVES {
+            return Err(crate::Error::new(
+                ErrorKind::InvalidInput,
+                format!("the UTXO have {leaves} leaves, but only {LEAVES} can be inserted"),
+            ));
+        }
+
         let snarks = Self::snarks(&utxo);
 
         let (agg_instances, proof) = accumulator_native(&snarks);
 
-        Self {
+        Ok(Self {
             // previous_agg,
             utxo,
             insert,
             agg_instances,
             proof,
-        }
+        })
+    }
+
+    /// The `(inputs, outputs)` shape of each aggregated UTXO
+    pub fn shapes(&self) -> Vec<(usize, usize)> {
+        self.utxo
+            .iter()
+            .map(|snark| shape(snark).expect("checked in new"))
+            .collect_vec()
     }
 
     fn snarks(utxo: &[Snark; UTXO_N]) -> Vec<&Snark> {
@@ -134,9 +179,14 @@ impl<const UTXO_N: usize, const MERKLE_D: usize, const LEAVES: usize>
             utxo_leafs.extend(snark_instances.iter().skip(3));
         }
 
+        // Every utxo leaf must be inserted, or it would be unconstrained
+        if utxo_leafs.len() > insert.leafs.len() {
+            return Err(Error::Synthesis);
+        }
+
         // Constrain utxo and insert leafs to be equal, only for the amount of utxo leafs that should exist. Remaining
         // insert leafs are for mints.
-        for (utxo_leaf, insert_leaf) in utxo_leafs.iter().zip(insert.leafs.iter()).take(LEAVES) {
+        for (utxo_leaf, insert_leaf) in utxo_leafs.iter().zip(insert.leafs.iter()) {
             layouter.assign_region(
                 || "leaf equality",
                 |mut region| {
@@ -200,15 +250,40 @@ impl<const UTXO_N: usize, const MERKLE_D: usize, const LEAVES: usize>
         self.insert.new_root()
     }
 
-    pub fn snark(&self, params: ParameterSet) -> Result<Snark, crate::Error> {
-        let pk = CircuitKind::AggUtxo.pk();
+    /// Proves with the [`CircuitKind::AggUtxo`] key, which is only for UTXO of the default shape.
+    ///
+    /// Returns an [`ErrorKind::KeyMismatch`] error for other shapes, use
+    /// [`AggregateUtxo::snark_with_key`] with a key from [`AggregateUtxo::keygen`] instead
+    pub fn snark(&self, params: ParameterSet) -> crate::Result<Snark> {
+        let default_shape = (UTXO_INPUTS, UTXO_OUTPUTS);
+        if self.shapes().iter().any(|shape| *shape != default_shape) {
+            return Err(crate::Error::new(
+                ErrorKind::KeyMismatch,
+                format!("the AggUtxo key only aggregates {default_shape:?} UTXO"),
+            ));
+        }
+
+        let pk = CircuitKind::AggUtxo.pk()?;
         Snark::create(
             Self::default(),
             vec![self.public_inputs()],
-            load_params(params),
+            load_params(params)?,
+            pk,
+        )
+    }
+
+    /// Proves with `pk`, a key generated for UTXO of the same shapes as this aggregate
+    pub fn snark_with_key(
+        &self,
+        params: ParameterSet,
+        pk: &ProvingKey<G1Affine>,
+    ) -> crate::Result<Snark> {
+        Snark::create(
+            self.clone(),
+            vec![self.public_inputs()],
+            load_params(params)?,
             pk,
         )
-        .map_err(crate::Error::err)
     }
 
     pub fn keygen(&self, params: ParameterSet) -> (ProvingKey<G1Affine>, VerifyingKey<G1Affine>) {
@@ -229,6 +304,6 @@ impl<const UTXO_N: usize, const MERKLE_D: usize, const LEAVES: usize> Default
 
         let insert = BatchInsert::default();
 
-        Self::new(utxo, insert)
+        Self::new(utxo, insert).unwrap()
     }
 }
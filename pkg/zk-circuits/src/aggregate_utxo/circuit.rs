@@ -30,6 +30,23 @@ pub struct AggregateUtxoCircuitConfig {
     is_less_than: IsLessThanChipConfig,
 }
 
+/// The config of the aggregation chip for `UTXO_N` UTXO proofs
+pub(crate) fn aggregation_params<const UTXO_N: usize>() -> AggregationChipConfigParams {
+    let num_advice = 2 + UTXO_N * 2;
+    let num_lookup_advice = 1 + num_advice / 12;
+
+    AggregationChipConfigParams {
+        strategy: halo2_ecc::fields::fp::FpStrategy::Simple,
+        degree: 21,
+        num_advice,
+        num_lookup_advice,
+        num_fixed: 1,
+        lookup_bits: 20,
+        limb_bits: 88,
+        num_limbs: 3,
+    }
+}
+
 impl<const UTXO_N: usize, const MERKLE_D: usize, const LEAVES: usize> Circuit<Fr>
     for AggregateUtxo<UTXO_N, MERKLE_D, LEAVES>
 {
@@ -44,20 +61,7 @@ impl<const UTXO_N: usize, const MERKLE_D: usize, const LEAVES: usize> Circuit<Fr
         let instance = meta.instance_column();
         meta.enable_equality(instance);
 
-        let num_advice = 2 + UTXO_N * 2;
-        let num_lookup_advice = 1 + num_advice / 12;
-        let params = AggregationChipConfigParams {
-            strategy: halo2_ecc::fields::fp::FpStrategy::Simple,
-            degree: 21,
-            num_advice,
-            num_lookup_advice,
-            num_fixed: 1,
-            lookup_bits: 20,
-            limb_bits: 88,
-            num_limbs: 3,
-        };
-
-        let aggregation_config = AggregationChip::configure(meta, params);
+        let aggregation_config = AggregationChip::configure(meta, aggregation_params::<UTXO_N>());
 
         // let advices = aggregation_config
         //     .base_field_config
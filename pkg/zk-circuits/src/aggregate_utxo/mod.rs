@@ -7,3 +7,4 @@ pub(crate) mod tests;
 
 // Main circuit
 pub use aggregate::*;
+pub(crate) use circuit::aggregation_params;
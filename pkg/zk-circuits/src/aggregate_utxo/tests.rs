@@ -1,4 +1,10 @@
-use crate::test::rollup::Rollup;
+use super::AggregateUtxo;
+use crate::{
+    constants::MERKLE_TREE_DEPTH,
+    data::{Note, ParameterSet, Utxo, UtxoKind},
+    test::rollup::Rollup,
+    CircuitKind, ErrorKind,
+};
 use halo2_base::halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr};
 
 #[test]
@@ -33,3 +39,76 @@ fn test_aggregate_utxo() {
 
     prover.assert_satisfied();
 }
+
+#[test]
+fn test_aggregate_mixed_utxo_shapes() {
+    let k = 21;
+
+    let mut rollup = Rollup::new();
+    let bob = rollup.new_wallet();
+    let alice = rollup.new_wallet();
+
+    // Add existing notes to the tree
+    let bob_notes = [10, 20, 30, 40].map(|amount| rollup.unverified_add_unspent_note(&bob, amount));
+    let alice_n1 = rollup.unverified_add_unspent_note(&alice, 100);
+
+    // Consolidate four of bob's notes into one note for alice
+    let consolidate = Utxo::<MERKLE_TREE_DEPTH, 4, 2>::new(
+        bob_notes.each_ref().map(|note| rollup.to_input_note(note)),
+        [alice.new_note(100), Note::padding_note()],
+        rollup.root_hash(),
+        UtxoKind::Transfer,
+    );
+
+    // Send 100 from alice to bob
+    let transfer = rollup.transfer(alice_n1, bob.new_note(100));
+
+    let padding = Utxo::<MERKLE_TREE_DEPTH>::new_padding();
+
+    let snarks = [
+        consolidate.snark(CircuitKind::Utxo4x2).unwrap(),
+        transfer.snark(CircuitKind::Utxo).unwrap(),
+        padding.snark(CircuitKind::Utxo).unwrap(),
+    ];
+    let batch_inserts = rollup.batch_inserts(
+        [consolidate.leafs(), transfer.leafs(), padding.leafs()]
+            .into_iter()
+            .flatten(),
+    );
+
+    let aggregate_utxo =
+        AggregateUtxo::<3, MERKLE_TREE_DEPTH, 14>::new(snarks, batch_inserts).unwrap();
+    assert_eq!(aggregate_utxo.shapes(), [(4, 2), (2, 2), (2, 2)]);
+
+    // 12 accumulator instances, the old and new roots, and 3 per UTXO, whatever their shape
+    let public_inputs = aggregate_utxo.public_inputs();
+    assert_eq!(public_inputs.len(), 12 + 2 + 3 * 3);
+
+    let prover = MockProver::<Fr>::run(k, &aggregate_utxo, vec![public_inputs]).unwrap();
+
+    prover.assert_satisfied();
+
+    let err = aggregate_utxo.snark(ParameterSet::TwentyOne).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::KeyMismatch);
+}
+
+#[test]
+fn test_aggregate_utxo_rejects_too_many_leaves() {
+    let mut rollup = Rollup::new();
+
+    let snarks = [
+        Utxo::<MERKLE_TREE_DEPTH, 8, 2>::new_padding()
+            .snark(CircuitKind::Utxo8x2)
+            .unwrap(),
+        Utxo::<MERKLE_TREE_DEPTH>::new_padding()
+            .snark(CircuitKind::Utxo)
+            .unwrap(),
+        Utxo::<MERKLE_TREE_DEPTH>::new_padding()
+            .snark(CircuitKind::Utxo)
+            .unwrap(),
+    ];
+    let batch_inserts = rollup.batch_inserts([]);
+
+    let err = AggregateUtxo::<3, MERKLE_TREE_DEPTH, 12>::new(snarks, batch_inserts).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
@@ -0,0 +1,101 @@
+//! Print the rows, columns and minimum `k` of each circuit, and of the chips shared between them
+//!
+//! ```sh
+//! cargo run --release --features cli --bin circuit_cost -- [--chips] [KIND]...
+//! ```
+//!
+//! The aggregation circuits are synthesized at `k = 21`, so they take a while.
+
+use std::process::ExitCode;
+
+use clap::Parser;
+use zk_circuits::{
+    cost::{chip_costs, circuit_cost, Columns},
+    CircuitKind,
+};
+
+#[derive(Debug, Parser)]
+struct Args {
+    /// Also print the cost of the chips shared between circuits
+    #[arg(long)]
+    chips: bool,
+
+    /// The circuits to measure, all circuits if none are given
+    #[arg(value_parser = parse_kind)]
+    kinds: Vec<CircuitKind>,
+}
+
+fn parse_kind(name: &str) -> Result<CircuitKind, String> {
+    CircuitKind::from_name(name).ok_or_else(|| {
+        let names = CircuitKind::ALL.map(|kind| kind.name());
+        format!("expected one of: {}", names.join(", "))
+    })
+}
+
+const COLUMNS_HEADER: &str = "advice  fixed  lookups  permutation  degree";
+
+fn columns(columns: &Columns) -> String {
+    format!(
+        "{:>6}  {:>5}  {:>7}  {:>11}  {:>6}",
+        columns.advice, columns.fixed, columns.lookups, columns.permutation, columns.degree
+    )
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let kinds = if args.kinds.is_empty() {
+        CircuitKind::ALL.to_vec()
+    } else {
+        args.kinds
+    };
+
+    let mut failed = 0;
+
+    println!(
+        "{:<12}  {:>2}  {:>5}  {:>8}  {:>8}  {COLUMNS_HEADER}",
+        "circuit", "k", "min_k", "rows", "headroom"
+    );
+
+    for kind in kinds {
+        match circuit_cost(kind) {
+            Ok(cost) => println!(
+                "{:<12}  {:>2}  {:>5}  {:>8}  {:>8}  {}",
+                kind.name(),
+                cost.k,
+                cost.min_k,
+                cost.rows,
+                cost.headroom(),
+                columns(&cost.columns)
+            ),
+            Err(err) => {
+                failed += 1;
+                eprintln!("{}: {err} {}", kind.name(), err.debug_repr());
+            }
+        }
+    }
+
+    if args.chips {
+        println!();
+        println!("{:<12}  {:>8}  {COLUMNS_HEADER}", "chip", "rows");
+
+        match chip_costs() {
+            Ok(chips) => {
+                for chip in chips {
+                    let rows = chip.rows.map_or("-".to_owned(), |rows| rows.to_string());
+                    println!("{:<12}  {rows:>8}  {}", chip.name, columns(&chip.columns));
+                }
+            }
+            Err(err) => {
+                failed += 1;
+                eprintln!("chips: {err} {}", err.debug_repr());
+            }
+        }
+    }
+
+    if failed > 0 {
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,77 @@
+//! Generate the proving and verifying keys for each circuit, and check the verifying keys against
+//! the pinned hashes in `src/keys/vk/manifest.json`
+//!
+//! ```sh
+//! cargo run --release --features cli --bin keygen -- --out-dir ./keys [KIND]...
+//! ```
+//!
+//! The output directory can be used as a [store][zk_circuits::store] directory.
+
+use std::{path::PathBuf, process::ExitCode};
+
+use clap::Parser;
+use zk_circuits::{store, CircuitKind};
+
+#[derive(Debug, Parser)]
+struct Args {
+    /// The directory to write the keys and their manifest to
+    #[arg(long)]
+    out_dir: PathBuf,
+
+    /// The circuits to generate keys for, all circuits if none are given
+    #[arg(value_parser = parse_kind)]
+    kinds: Vec<CircuitKind>,
+}
+
+fn parse_kind(name: &str) -> Result<CircuitKind, String> {
+    CircuitKind::from_name(name).ok_or_else(|| {
+        let names = CircuitKind::ALL.map(|kind| kind.name());
+        format!("expected one of: {}", names.join(", "))
+    })
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let kinds = if args.kinds.is_empty() {
+        CircuitKind::ALL.to_vec()
+    } else {
+        args.kinds
+    };
+
+    let manifest = match store::write_keys(&args.out_dir, &kinds) {
+        Ok(manifest) => manifest,
+        Err(err) => {
+            eprintln!("failed to write keys: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut unpinned = 0;
+
+    for kind in kinds {
+        let hash = manifest[&store::verifying_key_file_name(kind)].as_str();
+
+        match kind.pinned_vk_hash() {
+            Some(pinned) if pinned == hash => println!("{}: {hash}", kind.name()),
+            Some(pinned) => {
+                unpinned += 1;
+                println!("{}: {hash} (changed, pinned {pinned})", kind.name());
+            }
+            None => {
+                unpinned += 1;
+                println!("{}: {hash} (not pinned)", kind.name());
+            }
+        }
+    }
+
+    if unpinned > 0 {
+        eprintln!(
+            "{unpinned} verifying keys don't match the pinned hashes, if this is expected update \
+             them with `UPDATE_EXPECT=1 cargo test keys::tests::pinned_verifying_keys --release`"
+        );
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,107 @@
+//! Generate the EVM verifier for each circuit, with a manifest of the verifying key hashes they
+//! were generated from
+//!
+//! ```sh
+//! cargo run --release --features cli --bin verifiers -- --out-dir ./verifiers [--check] [KIND]...
+//! ```
+//!
+//! With `--check`, each verifier is compiled with `solc` (which must be on the `PATH`), written as
+//! `<name>_verifier.bin` (in the same format as `eth/contracts`), and called in an in-memory EVM
+//! with a fresh proof.
+
+use std::{fs, path::PathBuf, process::ExitCode};
+
+use clap::Parser;
+use zk_circuits::{evm_verifier, CircuitKind};
+
+#[derive(Debug, Parser)]
+struct Args {
+    /// The directory to write the verifiers and their manifest to
+    #[arg(long)]
+    out_dir: PathBuf,
+
+    /// Compile each verifier and check it against a fresh proof
+    #[arg(long)]
+    check: bool,
+
+    /// The circuits to generate verifiers for, the circuits verified on Ethereum if none are given
+    #[arg(value_parser = parse_kind)]
+    kinds: Vec<CircuitKind>,
+}
+
+fn parse_kind(name: &str) -> Result<CircuitKind, String> {
+    CircuitKind::from_name(name).ok_or_else(|| {
+        let names = CircuitKind::ALL.map(|kind| kind.name());
+        format!("expected one of: {}", names.join(", "))
+    })
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let kinds = if args.kinds.is_empty() {
+        vec![CircuitKind::AggAgg, CircuitKind::Burn, CircuitKind::Mint]
+    } else {
+        args.kinds
+    };
+
+    let verifiers = match evm_verifier::write_verifiers(&args.out_dir, &kinds) {
+        Ok(verifiers) => verifiers,
+        Err(err) => {
+            eprintln!("failed to write verifiers: {err} {}", err.debug_repr());
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let mut failed = 0;
+
+    for verifier in verifiers {
+        let name = verifier.kind.name();
+        println!("{name}: {} (vk {})", verifier.file_name(), verifier.vk_hash);
+
+        match verifier.kind.pinned_vk_hash() {
+            Some(pinned) if pinned == verifier.vk_hash => {}
+            Some(pinned) => {
+                println!("{name}: verifying key doesn't match the pinned hash {pinned}")
+            }
+            None => println!("{name}: verifying key isn't pinned"),
+        }
+
+        if !args.check {
+            continue;
+        }
+
+        let bytecode = match verifier.compile() {
+            Ok(bytecode) => bytecode,
+            Err(err) => {
+                failed += 1;
+                eprintln!("{name}: failed to compile: {err} {}", err.debug_repr());
+                continue;
+            }
+        };
+
+        let path = args.out_dir.join(format!("{name}_verifier.bin"));
+        if let Err(err) = fs::write(&path, hex::encode(&bytecode) + "\n") {
+            eprintln!("failed to write {}: {err}", path.display());
+            return ExitCode::FAILURE;
+        }
+
+        match verifier.check(bytecode) {
+            Ok(gas) => println!("{name}: verified a fresh proof, {gas} gas"),
+            Err(err) => {
+                failed += 1;
+                eprintln!(
+                    "{name}: failed to verify a fresh proof: {err} {}",
+                    err.debug_repr()
+                );
+            }
+        }
+    }
+
+    if failed > 0 {
+        eprintln!("{failed} verifiers failed the check");
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
@@ -137,7 +137,7 @@ impl<const L: usize> Burn<L> {
         params: &ParamsKZG<Bn256>,
         pk: &ProvingKey<G1Affine>,
         rng: impl RngCore,
-    ) -> Result<Proof, Error> {
+    ) -> crate::Result<Proof> {
         let circuit = Self::default();
         let instance = self.public_inputs();
         let instances = &[instance.as_slice()];
@@ -2,6 +2,7 @@ use crate::data::{Burn, ParameterSet};
 use crate::evm_verifier;
 use crate::test::{rollup::Rollup, util::get_params};
 use crate::util::keygen_from_params;
+use crate::CircuitKind;
 use halo2_base::halo2_proofs::dev::MockProver;
 use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
 use smirk::Element;
@@ -46,8 +47,67 @@ fn generate_verifier() {
 
     let (pk, _) = keygen_from_params(params_9, &circuit);
     let yul_code =
-        evm_verifier::generate_verifier(params_9, &pk, vec![circuit.public_inputs().len()]);
+        evm_verifier::generate_verifier(params_9, &pk, vec![circuit.public_inputs().len()])
+            .unwrap();
 
     let expected_yul_code = expect_test::expect_file!["./burn_verifier.yul"];
     expected_yul_code.assert_eq(&yul_code);
 }
+
+#[test]
+fn kind_verifier_matches_checked_in() {
+    let verifier = evm_verifier::EvmVerifier::generate(CircuitKind::Burn).unwrap();
+
+    let expected_yul_code = expect_test::expect_file!["./burn_verifier.yul"];
+    expected_yul_code.assert_eq(&verifier.yul);
+}
+
+#[test]
+fn verifier_verifies_fresh_proof() {
+    let verifier = evm_verifier::EvmVerifier::generate(CircuitKind::Burn).unwrap();
+
+    // The deployed bytecode is compiled from the checked-in Yul, which
+    // `kind_verifier_matches_checked_in` compares against the generated verifier
+    let gas = verifier
+        .check(evm_verifier::DeployedVerifier::Burn.bytecode())
+        .unwrap();
+    assert!(gas > 0);
+}
+
+#[test]
+#[ignore = "requires solc"]
+fn compiled_verifier_matches_deployed() {
+    let verifier = evm_verifier::EvmVerifier::generate(CircuitKind::Burn).unwrap();
+
+    assert_eq!(
+        verifier.compile().unwrap(),
+        evm_verifier::DeployedVerifier::Burn.bytecode()
+    );
+}
+
+#[test]
+fn deployed_verifier_verifies_proof() {
+    let mut rollup = Rollup::new();
+    let bob = rollup.new_wallet();
+    let bob_note = rollup.unverified_add_unspent_note(&bob, 100);
+
+    let burn = Burn {
+        notes: [bob_note.note()],
+        secret_key: bob.pk,
+        to_address: Element::ONE,
+    };
+    let proof = burn.evm_proof(ParameterSet::Nine).unwrap();
+    let mut instances = vec![burn.public_inputs()];
+
+    let gas = evm_verifier::DeployedVerifier::Burn
+        .verify(&instances, &proof)
+        .unwrap();
+    assert!(gas < 1_000_000, "burn verifier used {gas} gas");
+
+    // A different recipient must not verify
+    instances[0][0] = Fr::from(2);
+    let err = evm_verifier::DeployedVerifier::Burn
+        .verify(&instances, &proof)
+        .unwrap_err();
+    assert_eq!(err.kind(), crate::ErrorKind::InvalidProof);
+}
@@ -1,5 +1,6 @@
 use crate::{
     data::{ParameterSet, SnarkWitnessV1},
+    error::{catch_panic, Error, ErrorKind},
     params::load_params,
 };
 
@@ -7,7 +8,7 @@ use super::types::{PoseidonTranscript, Svk};
 use halo2_base::halo2_proofs::{
     circuit::Value,
     halo2curves::bn256::{Bn256, Fr, G1Affine},
-    plonk::{create_proof, Circuit, Error, ProvingKey, VerifyingKey},
+    plonk::{create_proof, Circuit, ProvingKey, VerifyingKey},
     poly::{
         commitment::ParamsProver,
         kzg::{
@@ -57,54 +58,58 @@ impl Snark {
         instances: Vec<Vec<Fr>>,
         params: &ParamsKZG<Bn256>,
         pk: &ProvingKey<G1Affine>,
-    ) -> Result<Snark, Error> {
-        let num_instance = instances.iter().map(|v| v.len()).collect_vec();
-
-        let protocol = compile(
-            params,
-            pk.get_vk(),
-            Config::kzg().with_num_instance(num_instance),
-        );
-        let slice = &instances
-            .iter()
-            .map(|instances| instances.as_slice())
-            .collect_vec();
-
-        let proof = {
-            let mut transcript = PoseidonTranscript::<NativeLoader, _>::init(Vec::new());
-            create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+    ) -> crate::Result<Snark> {
+        catch_panic(|| {
+            let num_instance = instances.iter().map(|v| v.len()).collect_vec();
+
+            let protocol = compile(
                 params,
-                pk,
-                &[circuit],
-                &[slice.as_slice()],
-                OsRng,
-                &mut transcript,
-            )
-            .unwrap();
-            transcript.finalize()
-        };
-
-        Ok(Self::new(protocol, instances.clone(), proof, params))
+                pk.get_vk(),
+                Config::kzg().with_num_instance(num_instance),
+            );
+            let slice = &instances
+                .iter()
+                .map(|instances| instances.as_slice())
+                .collect_vec();
+
+            let proof = {
+                let mut transcript = PoseidonTranscript::<NativeLoader, _>::init(Vec::new());
+                create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<_>, _, _, _, _>(
+                    params,
+                    pk,
+                    &[circuit],
+                    &[slice.as_slice()],
+                    OsRng,
+                    &mut transcript,
+                )?;
+                transcript.finalize()
+            };
+
+            Ok(Self::new(protocol, instances.clone(), proof, params))
+        })
     }
 
     pub fn from_witness(
         witness: SnarkWitnessV1,
         vk: &VerifyingKey<G1Affine>,
         params: ParameterSet,
-    ) -> Self {
-        let params = load_params(params);
+    ) -> crate::Result<Self> {
+        check_instance_columns(&witness, vk)?;
 
-        let num_instance = witness.instances.iter().map(|v| v.len()).collect_vec();
-        // let vk = keygen_vk(params, circuit).expect("keygen_vk should not fail");
-        let protocol = compile(params, vk, Config::kzg().with_num_instance(num_instance));
+        catch_panic(|| {
+            let params = load_params(params)?;
 
-        Self {
-            protocol,
-            instances: witness.fr_instances(),
-            proof: witness.proof,
-            svk: params.get_g()[0].into(),
-            dk: (params.g2(), params.s_g2()).into(),
-        }
+            let num_instance = witness.instances.iter().map(|v| v.len()).collect_vec();
+            let protocol = compile(params, vk, Config::kzg().with_num_instance(num_instance));
+
+            Ok(Self {
+                protocol,
+                instances: witness.fr_instances(),
+                proof: witness.proof,
+                svk: params.get_g()[0].into(),
+                dk: (params.g2(), params.s_g2()).into(),
+            })
+        })
     }
 
     pub fn to_witness(&self) -> SnarkWitnessV1 {
@@ -125,3 +130,21 @@ impl Snark {
         Value::known(&self.proof)
     }
 }
+
+/// Check that the witness has an instance column for each of the circuit's instance columns
+pub(crate) fn check_instance_columns(
+    witness: &SnarkWitnessV1,
+    vk: &VerifyingKey<G1Affine>,
+) -> crate::Result<()> {
+    let expected = vk.cs().num_instance_columns();
+    let actual = witness.instances.len();
+
+    if actual != expected {
+        return Err(Error::new(
+            ErrorKind::WrongInstanceCount,
+            format!("expected {expected} instance columns, got {actual}"),
+        ));
+    }
+
+    Ok(())
+}
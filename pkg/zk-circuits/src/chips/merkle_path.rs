@@ -10,7 +10,7 @@ use halo2_base::halo2_proofs::{
     halo2curves::bn256::Fr,
     plonk::Error,
 };
-use smirk::Element;
+use smirk::{Element, MerkleProof, ProofError};
 use std::iter::zip;
 
 impl<const DEPTH: usize> MerklePath<DEPTH> {
@@ -94,6 +94,13 @@ impl<const DEPTH: usize> MerklePath<DEPTH> {
     //     slice
     // }
 
+    /// Convert this path into a [`MerkleProof`] for `leaf`, using the bits of `leaf` as directions
+    ///
+    /// This fails if the path doesn't have `DEPTH - 1` siblings
+    pub fn to_merkle_proof(&self, leaf: Element) -> Result<MerkleProof<DEPTH>, ProofError> {
+        MerkleProof::new(leaf, self.siblings.clone())
+    }
+
     pub fn least_significant_bits(element: Element) -> impl Iterator<Item = bool> {
         element.lsb(DEPTH - 1).into_iter().rev()
     }
@@ -129,6 +136,23 @@ impl<const DEPTH: usize> MerklePath<DEPTH> {
     }
 }
 
+/// Convert an inclusion proof into a [`MerklePath`] for its leaf
+///
+/// A [`MerklePath`] derives its directions from the bits of the leaf, so this fails if the
+/// directions of the proof don't match them (e.g. for a proof from [`MerkleProof::for_key`] that a
+/// slot is empty)
+impl<const DEPTH: usize> TryFrom<MerkleProof<DEPTH>> for MerklePath<DEPTH> {
+    type Error = ProofError;
+
+    fn try_from(proof: MerkleProof<DEPTH>) -> Result<Self, Self::Error> {
+        proof.check_key(proof.leaf())?;
+
+        Ok(MerklePath {
+            siblings: proof.siblings().to_vec(),
+        })
+    }
+}
+
 pub struct MerklePathInclusionConstrainCells {
     pub root: AssignedCell<Fr, Fr>,
 }
@@ -268,4 +292,25 @@ mod tests {
 
         assert_eq!(root, expected_root);
     }
+
+    #[test]
+    fn merkle_proof_matches_compute_root() {
+        let path = MerklePath::<64>::default().apply_leaf(Element::from(7u64));
+        let leaf = Element::from(12u64);
+
+        let proof = path.to_merkle_proof(leaf).unwrap();
+        assert_eq!(proof.compute_root(), path.compute_root(leaf));
+        assert_eq!(proof.verify(path.compute_root(leaf)), Ok(()));
+
+        let path_again = MerklePath::try_from(proof).unwrap();
+        assert_eq!(path_again.siblings, path.siblings);
+
+        // a proof for the slot of 13, whose directions don't match the bits of the leaf 12
+        let other_slot =
+            MerkleProof::<64>::for_key(Element::from(13u64), leaf, path.siblings).unwrap();
+        assert_eq!(
+            MerklePath::try_from(other_slot).unwrap_err(),
+            ProofError::DirectionMismatch { level: 0 }
+        );
+    }
 }
@@ -1,6 +1,8 @@
 use super::Compliance;
 use crate::chips::{
+    binary_decomposition::BinaryDecompositionConfig,
     is_constant::{IsConstantChip, IsConstantConfig},
+    is_less_than::{IsLessThanChip, IsLessThanChipConfig},
     poseidon::{P128Pow5T3Fr, PoseidonChip, PoseidonConfig},
     swap::{CondSwapChip, CondSwapConfig},
 };
@@ -17,6 +19,8 @@ pub struct ComplianceCircuitConfig {
     poseidon_config: PoseidonConfig<Fr, 3, 2>,
     swap_config: CondSwapConfig,
     is_zero_config: IsConstantConfig<Fr>,
+    binary_decomposition_config: BinaryDecompositionConfig<Fr, 1>,
+    is_less_than_config: IsLessThanChipConfig,
 }
 
 impl<const N: usize> Circuit<Fr> for Compliance<N> {
@@ -69,12 +73,21 @@ impl<const N: usize> Circuit<Fr> for Compliance<N> {
         let is_zero_config =
             IsConstantChip::configure(meta, advices[0], advices[1], advices[2], Fr::zero());
 
+        let q_range_check = meta.selector();
+        let binary_decomposition_config =
+            BinaryDecompositionConfig::configure(meta, q_range_check, advices[0], advices[1]);
+
+        let is_less_than_config =
+            IsLessThanChip::configure(meta, [advices[0], advices[1], advices[2], advices[3]]);
+
         ComplianceCircuitConfig {
             advices,
             instance,
             poseidon_config,
             swap_config,
             is_zero_config,
+            binary_decomposition_config,
+            is_less_than_config,
         }
     }
 
@@ -91,6 +104,8 @@ impl<const N: usize> Circuit<Fr> for Compliance<N> {
             config.poseidon_config,
             CondSwapChip::construct(config.swap_config),
             IsConstantChip::construct(config.is_zero_config),
+            config.binary_decomposition_config,
+            IsLessThanChip::construct(config.is_less_than_config),
         )?;
 
         Ok(())
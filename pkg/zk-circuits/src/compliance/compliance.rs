@@ -1,34 +1,42 @@
 use crate::{
     chips::{
-        is_constant::IsConstantChip, merkle_path::MerklePathInclusionConstrainCells,
+        aggregation::snark::Snark, binary_decomposition::BinaryDecompositionConfig,
+        is_constant::IsConstantChip, is_less_than::IsLessThanChip, merkle_path::merkle_root,
         poseidon::PoseidonConfig, swap::CondSwapChip,
     },
     data::{MerklePath, Note, ParameterSet},
-    util::{assign_constant, keygen_from_params},
+    params::load_params,
+    util::{assign_constant, assign_private_input, keygen_from_params},
+    CircuitKind,
 };
 use halo2_base::halo2_proofs::{
-    circuit::Layouter,
+    circuit::{AssignedCell, Layouter, Value},
     halo2curves::bn256::{Fr, G1Affine},
     plonk::{Advice, Column, Error, Instance, ProvingKey, VerifyingKey},
 };
+use zk_primitives::Element;
 
 /// Compliance proves that the source of a note was not from a known bad actor
+///
+/// Denied sources are stored in a sparse merkle tree (the denylist), where each source is inserted
+/// at the position given by its least significant bits. A source is compliant if the leaf at its
+/// position is null, which is proven by computing the denylist root from a null leaf and the merkle
+/// path to that position.
 #[derive(Clone, Debug, Default)]
 pub struct Compliance<const N: usize> {
     /// Note that we want to prove compliance for
     note: Note,
 
     /// Expected recent root of the compliance merkle tree
-    #[allow(unused)]
-    recent_root: Fr,
+    recent_root: Element,
 
-    /// Merkle tree path for compliance merkle tree, so we can prove that the source does not exist in the
-    /// merkle tree
+    /// Merkle tree path to the position of the note's source in the compliance merkle tree, so we
+    /// can prove that the source does not exist in the merkle tree
     merkle_path: MerklePath<N>,
 }
 
 impl<const N: usize> Compliance<N> {
-    pub fn new(note: Note, recent_root: Fr, merkle_path: MerklePath<N>) -> Self {
+    pub fn new(note: Note, recent_root: Element, merkle_path: MerklePath<N>) -> Self {
         Self {
             note,
             recent_root,
@@ -36,8 +44,8 @@ impl<const N: usize> Compliance<N> {
         }
     }
 
-    /// Enforces constraints for the input note (includes default note constraints, plus additional
-    /// constraints to prove spending of note is allowable)
+    /// Enforces constraints for the note (includes default note constraints), plus constraints
+    /// to prove that the note's source is not in the denylist
     #[allow(clippy::too_many_arguments)]
     pub fn enforce_constraints(
         &self,
@@ -47,11 +55,13 @@ impl<const N: usize> Compliance<N> {
         poseidon_config: PoseidonConfig<Fr, 3, 2>,
         swap_chip: CondSwapChip<Fr>,
         is_zero_chip: IsConstantChip<Fr>,
+        decompose: BinaryDecompositionConfig<Fr, 1>,
+        less_than_chip: IsLessThanChip<Fr>,
     ) -> Result<(), Error> {
-        // First we need to check the std note constraints
-        // TODO(sec): update note to include SOURCE
+        // First we need to check the std note constraints, the source cell comes from the
+        // commitment so it can't differ from the note's source
         let note_commitment_cells = self.note.enforce_constraints(
-            layouter.namespace(|| "input note enforce commitment"),
+            layouter.namespace(|| "note enforce commitment"),
             advice,
             poseidon_config.clone(),
             is_zero_chip,
@@ -66,19 +76,82 @@ impl<const N: usize> Compliance<N> {
             Fr::zero(),
         )?;
 
-        // Check input note commitment is in an existing merkle root
-        let MerklePathInclusionConstrainCells { root } =
-            self.merkle_path.enforce_inclusion_constraints(
-                layouter.namespace(|| "leaf in tree"),
-                // TODO(sec): this should come from note_commitment_cells
-                self.note.source().into(),
-                null_leaf,
-                poseidon_config,
-                swap_chip,
-            )?;
+        // Binary decomposition of the source, the bits are the position of the source in the tree
+        let decomposed_bits = layouter.assign_region(
+            || "decompose source",
+            |mut region| {
+                decompose.copy_decompose(
+                    &mut region,
+                    0,
+                    note_commitment_cells.source.clone(),
+                    256,
+                    256,
+                )
+            },
+        )?;
+
+        let zero = assign_constant(
+            || "assign zero bit",
+            layouter.namespace(|| "zero bit"),
+            advice,
+            Fr::from(0),
+        )?;
+
+        let one: AssignedCell<Fr, Fr> = assign_constant(
+            || "assign one bit",
+            layouter.namespace(|| "one bit"),
+            advice,
+            Fr::from(1),
+        )?;
+
+        // Ensure the decomposition is of the canonical source, otherwise a prover could decompose
+        // source + modulus to prove a different position
+        less_than_chip.assign(
+            layouter.namespace(|| "less than modulus"),
+            &Element::MODULUS
+                .to_be_bits()
+                .iter()
+                .map(|b| if *b { one.clone() } else { zero.clone() })
+                .collect::<Vec<_>>(),
+            &decomposed_bits
+                .clone()
+                .into_iter()
+                .rev()
+                .collect::<Vec<_>>(),
+        )?;
+
+        // Witness all siblings
+        let sibling_witnesses = self
+            .merkle_path
+            .siblings
+            .iter()
+            .map(|w| {
+                assign_private_input(
+                    || "sibling witness",
+                    layouter.namespace(|| "sibling witness"),
+                    advice,
+                    Value::known(w.to_base()),
+                )
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        // Merge siblings with decomposed bits
+        let siblings = sibling_witnesses
+            .iter()
+            .zip(decomposed_bits.iter().take(N - 1))
+            .collect::<Vec<_>>();
+
+        // Compute the root with a null leaf at the source's position
+        let root = merkle_root(
+            layouter.namespace(|| "null root"),
+            swap_chip,
+            poseidon_config,
+            null_leaf,
+            &siblings,
+        )?;
 
         // Constrain calculated root from null merkle path to be equal to the recent root
-        // provided. Recent root must be checked against the compliance merkle tre.
+        // provided. Recent root must be checked against the compliance merkle tree.
         layouter.constrain_instance(root.cell(), instance, 0)?;
 
         // Constrain the note commitment, so we know which note to allow
@@ -87,6 +160,24 @@ impl<const N: usize> Compliance<N> {
         Ok(())
     }
 
+    /// Whether the note's source is absent from the denylist with root `recent_root`
+    pub fn is_compliant(&self) -> bool {
+        self.merkle_path.compute_null_root(self.note.source()) == self.recent_root
+    }
+
+    /// Public inputs to be used in proof
+    ///  [recent_root, note_commitment]
+    pub fn public_inputs(&self) -> Vec<Fr> {
+        vec![self.recent_root.into(), self.note.commitment().into()]
+    }
+
+    pub fn snark(&self, kind: CircuitKind) -> crate::Result<Snark> {
+        let kzg_params = load_params(kind.params())?;
+        let (pk, _) = self.keygen(kind.params());
+
+        Snark::create(self.clone(), vec![self.public_inputs()], kzg_params, &pk)
+    }
+
     pub fn keygen(&self, params: ParameterSet) -> (ProvingKey<G1Affine>, VerifyingKey<G1Affine>) {
         keygen_from_params(params, self)
     }
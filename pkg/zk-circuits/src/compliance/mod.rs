@@ -1,6 +1,8 @@
 mod circuit;
 #[allow(clippy::module_inception)]
 mod compliance;
+#[cfg(test)]
+mod tests;
 
 // Main circuit
 pub use compliance::*;
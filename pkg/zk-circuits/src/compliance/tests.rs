@@ -0,0 +1,92 @@
+use crate::{
+    compliance::Compliance, constants::MERKLE_TREE_DEPTH, data::Note, test::rollup::merkle_path,
+    util::insecure_random_element, CircuitKind,
+};
+use halo2_base::halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr};
+use smirk::Tree;
+use zk_primitives::Element;
+
+/// A denylist containing some random sources, plus `denied`
+fn denylist(denied: Element) -> Tree<MERKLE_TREE_DEPTH, ()> {
+    let mut tree = Tree::new();
+
+    for _ in 0..3 {
+        tree.insert(insecure_random_element(), ()).unwrap();
+    }
+
+    tree.insert(denied, ()).unwrap();
+    tree
+}
+
+fn compliance(tree: &Tree<MERKLE_TREE_DEPTH, ()>, note: Note) -> Compliance<MERKLE_TREE_DEPTH> {
+    let path = merkle_path(tree, note.source());
+    Compliance::new(note, tree.root_hash(), path)
+}
+
+#[test]
+fn test_compliant_note() {
+    let k = 14;
+
+    let tree = denylist(insecure_random_element());
+    let note = Note::new(insecure_random_element(), Element::from(100u64));
+
+    let circuit = compliance(&tree, note.clone());
+    assert!(circuit.is_compliant());
+
+    let public_input = circuit.public_inputs();
+    assert_eq!(
+        public_input,
+        vec![tree.root_hash().into(), note.commitment().into()]
+    );
+
+    // Prove mock
+    let prover = MockProver::<Fr>::run(k, &circuit, vec![public_input]).unwrap();
+    prover.assert_satisfied();
+
+    // Prove for real circuit
+    let snark = circuit.snark(CircuitKind::Compliance).unwrap();
+    snark.to_witness().verify(CircuitKind::Compliance).unwrap();
+}
+
+#[test]
+fn test_denied_note() {
+    let k = 14;
+
+    let note = Note::new(insecure_random_element(), Element::from(100u64));
+    let tree = denylist(note.source());
+
+    let circuit = compliance(&tree, note);
+    assert!(!circuit.is_compliant());
+
+    // The root computed with a null leaf at the source's position isn't the denylist root
+    let prover = MockProver::<Fr>::run(k, &circuit, vec![circuit.public_inputs()]).unwrap();
+    assert!(prover.verify().is_err());
+}
+
+#[test]
+fn test_source_is_bound_to_commitment() {
+    let k = 14;
+
+    let note = Note::new(insecure_random_element(), Element::from(100u64));
+    let tree = denylist(note.source());
+
+    // Witness the denied note with a compliant source, and a path that is valid for that source
+    let fake_note = Note {
+        source: insecure_random_element(),
+        ..note.clone()
+    };
+    let circuit = compliance(&tree, fake_note);
+    assert!(circuit.is_compliant());
+
+    // The fake source is compliant, so it is only rejected because it isn't the committed source
+    let prover = MockProver::<Fr>::run(k, &circuit, vec![circuit.public_inputs()]).unwrap();
+    prover.assert_satisfied();
+
+    let prover = MockProver::<Fr>::run(
+        k,
+        &circuit,
+        vec![vec![tree.root_hash().into(), note.commitment().into()]],
+    )
+    .unwrap();
+    assert!(prover.verify().is_err());
+}
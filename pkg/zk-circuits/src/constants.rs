@@ -2,9 +2,15 @@
 /// likihood of collisions
 pub const MERKLE_TREE_DEPTH: usize = 161;
 
+/// The number of inputs of the default UTXO shape
 pub const UTXO_INPUTS: usize = 2;
+/// The number of outputs of the default UTXO shape
 pub const UTXO_OUTPUTS: usize = 2;
 
+/// The `(inputs, outputs)` shapes of UTXO that have keys (see [`crate::CircuitKind::utxo`]),
+/// larger shapes let a wallet consolidate many small notes in one transaction
+pub const UTXO_SHAPES: [(usize, usize); 3] = [(UTXO_INPUTS, UTXO_OUTPUTS), (4, 2), (8, 2)];
+
 pub const UTXO_AGG_NUMBER: usize = 3;
 pub const UTXO_AGG_LEAVES: usize = UTXO_AGG_NUMBER * (UTXO_INPUTS + UTXO_OUTPUTS);
 
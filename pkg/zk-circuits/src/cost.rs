@@ -0,0 +1,391 @@
+//! The cost and layout of each circuit
+//!
+//! Each [`CircuitKind`] is synthesized with its default witness in the [`MockProver`], at the `k`
+//! of its [`ParameterSet`], to count the rows that are assigned. The columns, lookups and
+//! permutation come from the circuit's [`ConstraintSystem`]. The smallest `k` that fits the rows
+//! shows how much headroom a circuit has before it needs a larger SRS.
+//!
+//! [`chip_costs`] gives the same breakdown for the chips that are shared between circuits,
+//! configured on their own.
+
+use eth_types::sign_types::SignData;
+use halo2_base::halo2_proofs::{
+    circuit::{Layouter, SimpleFloorPlanner, Value},
+    dev::{CellValue, MockProver},
+    halo2curves::bn256::Fr,
+    plonk::{Advice, Circuit, Column, ConstraintSystem, Error},
+};
+use zk_primitives::Element;
+
+use crate::{
+    aggregate_utxo::{aggregation_params, AggregateUtxo},
+    chips::{
+        aggregation::aggregate::AggregationChip,
+        poseidon::{poseidon_hash_gadget, P128Pow5T3Fr, PoseidonChip, PoseidonConfig},
+        sig::{SignatureChip, SignatureChipConfig},
+        swap::{CondSwapChip, CondSwapConfig},
+    },
+    compliance::Compliance,
+    constants::MERKLE_TREE_DEPTH,
+    data::{AggregateAgg, Burn, MerklePath, Mint, ParameterSet, Points, Signature, Utxo},
+    error::catch_panic,
+    util::assign_private_input,
+    CircuitKind,
+};
+
+/// The columns and arguments of a [`ConstraintSystem`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Columns {
+    pub advice: usize,
+    pub fixed: usize,
+    pub instance: usize,
+    pub selectors: usize,
+    /// The number of lookup arguments
+    pub lookups: usize,
+    /// The number of columns with equality enabled
+    pub permutation: usize,
+    /// The maximum degree of the gates and arguments
+    pub degree: usize,
+}
+
+impl Columns {
+    pub fn of(cs: &ConstraintSystem<Fr>) -> Self {
+        Self {
+            advice: cs.num_advice_columns(),
+            fixed: cs.num_fixed_columns(),
+            instance: cs.num_instance_columns(),
+            selectors: cs.num_selectors(),
+            lookups: cs.lookups().len(),
+            permutation: cs.permutation().get_columns().len(),
+            degree: cs.degree(),
+        }
+    }
+}
+
+/// The cost of a circuit, see the [module docs][self]
+#[derive(Debug, Clone)]
+pub struct CircuitCost {
+    pub kind: CircuitKind,
+    /// The `k` of the circuit's [`ParameterSet`]
+    pub k: u32,
+    /// The smallest `k` that the assigned rows fit in
+    pub min_k: u32,
+    /// The number of rows assigned in any advice or fixed column (including lookup tables)
+    pub rows: usize,
+    /// The number of rows that can be assigned at `k`, which excludes the blinding rows
+    pub usable_rows: usize,
+    pub columns: Columns,
+}
+
+impl CircuitCost {
+    /// The number of rows left before the circuit needs a larger [`ParameterSet`]
+    pub fn headroom(&self) -> usize {
+        self.usable_rows.saturating_sub(self.rows)
+    }
+}
+
+/// The cost of a chip, configured on its own
+#[derive(Debug, Clone)]
+pub struct ChipCost {
+    pub name: &'static str,
+    /// The number of rows used by one use of the chip, or `None` if the chip can't be synthesized
+    /// on its own (e.g. aggregation, which needs proofs to aggregate)
+    pub rows: Option<usize>,
+    pub columns: Columns,
+}
+
+macro_rules! measure {
+    ($kind:expr, $circuit:ty) => {{
+        let circuit = <$circuit>::default();
+        let instances = vec![circuit.public_inputs()];
+        measure($kind, circuit, instances)
+    }};
+}
+
+/// Synthesize the default witness of `kind`, and measure its cost
+///
+/// This runs the [`MockProver`] at the `k` of the circuit's [`ParameterSet`], so it is slow for
+/// the aggregation circuits, and fails if the circuit doesn't fit
+pub fn circuit_cost(kind: CircuitKind) -> crate::Result<CircuitCost> {
+    match kind {
+        CircuitKind::Signature => measure!(kind, Signature),
+        CircuitKind::Points => measure!(kind, Points),
+        CircuitKind::Utxo => measure!(kind, Utxo::<161>),
+        CircuitKind::Utxo4x2 => measure!(kind, Utxo::<161, 4, 2>),
+        CircuitKind::Utxo8x2 => measure!(kind, Utxo::<161, 8, 2>),
+        CircuitKind::AggUtxo => measure!(kind, AggregateUtxo::<3, 161, 12>),
+        CircuitKind::AggAgg => {
+            let circuit = AggregateAgg::<2>::default();
+            let instances = vec![circuit.public_inputs()?];
+            measure(kind, circuit, instances)
+        }
+        CircuitKind::Burn => measure!(kind, Burn::<1>),
+        CircuitKind::Mint => measure!(kind, Mint::<1>),
+        CircuitKind::Compliance => measure!(kind, Compliance::<161>),
+    }
+}
+
+fn measure<C: Circuit<Fr>>(
+    kind: CircuitKind,
+    circuit: C,
+    instances: Vec<Vec<Fr>>,
+) -> crate::Result<CircuitCost> {
+    let k = kind.params().k();
+    let (rows, cs) = assigned_rows(k, &circuit, instances)?;
+
+    Ok(CircuitCost {
+        kind,
+        k,
+        min_k: min_k(rows, &cs),
+        rows,
+        usable_rows: (1 << k) - (cs.blinding_factors() + 1),
+        columns: Columns::of(&cs),
+    })
+}
+
+/// The costs of the chips that are shared between circuits
+pub fn chip_costs() -> crate::Result<Vec<ChipCost>> {
+    let (poseidon_rows, poseidon) = assigned_rows(10, &PoseidonHarness, vec![])?;
+    let (merkle_path_rows, merkle_path) = assigned_rows(
+        ParameterSet::Fourteen.k(),
+        &MerklePathHarness::default(),
+        vec![],
+    )?;
+
+    let (signature_rows, signature) = assigned_rows(20, &SignatureHarness, vec![])?;
+
+    let mut aggregation = ConstraintSystem::default();
+    AggregationChip::configure(&mut aggregation, aggregation_params::<3>());
+
+    Ok(vec![
+        ChipCost {
+            name: "poseidon",
+            rows: Some(poseidon_rows),
+            columns: Columns::of(&poseidon),
+        },
+        ChipCost {
+            name: "merkle_path",
+            rows: Some(merkle_path_rows),
+            columns: Columns::of(&merkle_path),
+        },
+        ChipCost {
+            name: "aggregation",
+            rows: None,
+            columns: Columns::of(&aggregation),
+        },
+        ChipCost {
+            name: "signature",
+            rows: Some(signature_rows),
+            columns: Columns::of(&signature),
+        },
+    ])
+}
+
+/// Run the [`MockProver`], returning the number of rows assigned and the constraint system
+fn assigned_rows<C: Circuit<Fr>>(
+    k: u32,
+    circuit: &C,
+    instances: Vec<Vec<Fr>>,
+) -> crate::Result<(usize, ConstraintSystem<Fr>)> {
+    catch_panic(|| {
+        let prover = MockProver::run(k, circuit, instances)?;
+
+        let rows = prover
+            .advice()
+            .iter()
+            .chain(prover.fixed())
+            .filter_map(|column| {
+                column
+                    .iter()
+                    .rposition(|cell| matches!(cell, CellValue::Assigned(_)))
+            })
+            .max()
+            .map_or(0, |row| row + 1);
+
+        Ok((rows, prover.cs().clone()))
+    })
+}
+
+/// The smallest `k` with enough usable rows for `rows`, after the blinding rows
+fn min_k(rows: usize, cs: &ConstraintSystem<Fr>) -> u32 {
+    let needed = (rows + cs.blinding_factors() + 1).max(cs.minimum_rows());
+    needed.next_power_of_two().trailing_zeros()
+}
+
+/// Configure the advice and fixed columns that the UTXO circuits use for Poseidon
+fn configure_poseidon(
+    meta: &mut ConstraintSystem<Fr>,
+) -> ([Column<Advice>; 5], PoseidonConfig<Fr, 3, 2>) {
+    let advices = [(); 5].map(|_| meta.advice_column());
+    for advice in advices {
+        meta.enable_equality(advice);
+    }
+
+    let lagrange_coeffs = [(); 6].map(|_| meta.fixed_column());
+    meta.enable_constant(lagrange_coeffs[0]);
+
+    let poseidon_config = PoseidonChip::configure::<P128Pow5T3Fr>(
+        meta,
+        advices[1..4].try_into().unwrap(),
+        advices[0],
+        lagrange_coeffs[0..3].try_into().unwrap(),
+        lagrange_coeffs[3..6].try_into().unwrap(),
+    );
+
+    (advices, poseidon_config)
+}
+
+/// One hash of two elements, as in a merkle path
+#[derive(Debug, Clone, Copy, Default)]
+struct PoseidonHarness;
+
+impl Circuit<Fr> for PoseidonHarness {
+    type Config = ([Column<Advice>; 5], PoseidonConfig<Fr, 3, 2>);
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        configure_poseidon(meta)
+    }
+
+    fn synthesize(
+        &self,
+        (advices, poseidon_config): Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let left = assign_private_input(
+            || "left",
+            layouter.namespace(|| "left"),
+            advices[0],
+            Value::known(Fr::zero()),
+        )?;
+        let right = assign_private_input(
+            || "right",
+            layouter.namespace(|| "right"),
+            advices[1],
+            Value::known(Fr::zero()),
+        )?;
+
+        poseidon_hash_gadget(
+            poseidon_config,
+            layouter.namespace(|| "hash"),
+            [left, right],
+        )?;
+
+        Ok(())
+    }
+}
+
+/// One inclusion proof in a tree of depth [`MERKLE_TREE_DEPTH`]
+#[derive(Debug, Clone, Default)]
+struct MerklePathHarness {
+    path: MerklePath<MERKLE_TREE_DEPTH>,
+}
+
+impl Circuit<Fr> for MerklePathHarness {
+    type Config = (
+        [Column<Advice>; 5],
+        PoseidonConfig<Fr, 3, 2>,
+        CondSwapConfig,
+    );
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        let (advices, poseidon_config) = configure_poseidon(meta);
+        let swap_config = CondSwapChip::configure(meta, advices);
+
+        (advices, poseidon_config, swap_config)
+    }
+
+    fn synthesize(
+        &self,
+        (advices, poseidon_config, swap_config): Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        let leaf = Element::ZERO.to_base();
+        let leaf_assigned = assign_private_input(
+            || "leaf",
+            layouter.namespace(|| "leaf"),
+            advices[0],
+            Value::known(leaf),
+        )?;
+
+        self.path.enforce_inclusion_constraints(
+            layouter.namespace(|| "merkle path"),
+            leaf,
+            leaf_assigned,
+            poseidon_config,
+            CondSwapChip::construct(swap_config),
+        )?;
+
+        Ok(())
+    }
+}
+
+/// One ECDSA verification, of the dummy signature that the chip pads its keccak inputs with
+#[derive(Debug, Clone, Copy, Default)]
+struct SignatureHarness;
+
+impl Circuit<Fr> for SignatureHarness {
+    type Config = SignatureChipConfig<Fr>;
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self
+    }
+
+    fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+        SignatureChipConfig::configure(meta)
+    }
+
+    fn synthesize(
+        &self,
+        config: Self::Config,
+        mut layouter: impl Layouter<Fr>,
+    ) -> Result<(), Error> {
+        SignatureChip::construct(config).verify(&mut layouter, &[SignData::default()])?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature_fits_its_params() {
+        let cost = circuit_cost(CircuitKind::Signature).unwrap();
+
+        assert_eq!(cost.k, 6);
+        assert!(cost.rows > 0);
+        assert!(cost.min_k <= cost.k);
+        assert_eq!(cost.columns.instance, 1);
+    }
+
+    #[test]
+    fn chip_costs_are_measured() {
+        let chips = chip_costs().unwrap();
+        let rows = |name| chips.iter().find(|chip| chip.name == name).unwrap().rows;
+
+        assert!(rows("merkle_path").unwrap() > rows("poseidon").unwrap());
+        assert!(rows("signature").unwrap() > rows("merkle_path").unwrap());
+        assert_eq!(rows("aggregation"), None);
+    }
+
+    #[test]
+    fn min_k_leaves_room_for_blinding() {
+        let cs = ConstraintSystem::<Fr>::default();
+        let blinding = cs.blinding_factors() + 1;
+
+        assert_eq!(min_k(64 - blinding, &cs), 6);
+        assert_eq!(min_k(64 - blinding + 1, &cs), 7);
+    }
+}
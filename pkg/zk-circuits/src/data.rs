@@ -2,7 +2,10 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 use smirk::Element;
 
-use crate::{aggregate_utxo::AggregateUtxo, Snark, UTXO_INPUTS, UTXO_OUTPUTS};
+use crate::{
+    aggregate_utxo::AggregateUtxo, note_encryption::NoteCiphertext, Snark, UTXO_INPUTS,
+    UTXO_OUTPUTS,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ParameterSet {
@@ -10,6 +13,8 @@ pub enum ParameterSet {
     Eight,
     Nine,
     Fourteen,
+    Fifteen,
+    Sixteen,
     TwentyOne,
 }
 
@@ -114,10 +119,27 @@ pub struct InputNote<const MERKLE_D: usize> {
     pub merkle_path: MerklePath<MERKLE_D>,
 }
 
+/// A UTXO transaction with `INPUTS` input notes and `OUTPUTS` output notes
+///
+/// Each shape of UTXO is a separate circuit with its own keys, so only the shapes in
+/// [`UTXO_SHAPES`][crate::constants::UTXO_SHAPES] have a [`CircuitKind`][crate::CircuitKind]
+/// (see [`Utxo::circuit_kind`]). [`AggregateUtxo`] aggregates UTXO of any of these shapes
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct Utxo<const MERKLE_D: usize> {
-    pub inputs: [InputNote<MERKLE_D>; UTXO_INPUTS],
-    pub outputs: [Note; UTXO_OUTPUTS],
+pub struct Utxo<
+    const MERKLE_D: usize,
+    const INPUTS: usize = UTXO_INPUTS,
+    const OUTPUTS: usize = UTXO_OUTPUTS,
+> {
+    #[serde(
+        serialize_with = "crate::util::serialize_array",
+        deserialize_with = "crate::util::deserialize_array"
+    )]
+    pub inputs: [InputNote<MERKLE_D>; INPUTS],
+    #[serde(
+        serialize_with = "crate::util::serialize_array",
+        deserialize_with = "crate::util::deserialize_array"
+    )]
+    pub outputs: [Note; OUTPUTS],
 
     /// Merkle root of the input notes (required to prove that input notes already
     /// exist in the tree and can therefore be spent)
@@ -127,7 +149,9 @@ pub struct Utxo<const MERKLE_D: usize> {
     pub kind: UtxoKind,
 }
 
-impl<const MERKLE_D: usize> Default for Utxo<MERKLE_D> {
+impl<const MERKLE_D: usize, const INPUTS: usize, const OUTPUTS: usize> Default
+    for Utxo<MERKLE_D, INPUTS, OUTPUTS>
+{
     fn default() -> Self {
         Self {
             inputs: core::array::from_fn(|_| InputNote::default()),
@@ -148,18 +172,13 @@ pub enum UtxoKind {
 }
 
 #[derive(
-    Debug,
-    Default,
-    Clone,
-    Hash,
-    PartialEq,
-    Eq,
-    Serialize,
-    Deserialize,
-    BorshSerialize,
-    BorshDeserialize,
+    Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize, BorshSerialize, BorshDeserialize,
 )]
-pub struct UTXOProof<const MERKLE_D: usize> {
+pub struct UTXOProof<
+    const MERKLE_D: usize,
+    const INPUTS: usize = UTXO_INPUTS,
+    const OUTPUTS: usize = UTXO_OUTPUTS,
+> {
     /// Root hash
     pub recent_root: Element,
     /// Mint/Burn hash (null for transfer)
@@ -167,20 +186,53 @@ pub struct UTXOProof<const MERKLE_D: usize> {
     /// Mint/Burn value (null for transfer)
     pub mb_value: Element,
     /// Leaves
-    pub input_leaves: [Element; UTXO_INPUTS],
-    pub output_leaves: [Element; UTXO_OUTPUTS],
+    #[serde(
+        serialize_with = "crate::util::serialize_array",
+        deserialize_with = "crate::util::deserialize_array"
+    )]
+    pub input_leaves: [Element; INPUTS],
+    #[serde(
+        serialize_with = "crate::util::serialize_array",
+        deserialize_with = "crate::util::deserialize_array"
+    )]
+    pub output_leaves: [Element; OUTPUTS],
+    /// The note encrypted to its recipient for each output leaf, or `None` for padding notes and
+    /// outputs without a recipient (see [`UTXOProof::with_output_memos`])
+    #[serde(
+        default = "crate::util::none_array",
+        serialize_with = "crate::util::serialize_array",
+        deserialize_with = "crate::util::deserialize_array"
+    )]
+    pub output_memos: [Option<NoteCiphertext>; OUTPUTS],
     /// Proof
     pub proof: Vec<u8>,
 }
 
+// https://github.com/rust-lang/rust/issues/61415
+impl<const MERKLE_D: usize, const INPUTS: usize, const OUTPUTS: usize> Default
+    for UTXOProof<MERKLE_D, INPUTS, OUTPUTS>
+{
+    fn default() -> Self {
+        Self {
+            recent_root: Element::default(),
+            mb_hash: Element::default(),
+            mb_value: Element::default(),
+            input_leaves: [Element::default(); INPUTS],
+            output_leaves: [Element::default(); OUTPUTS],
+            output_memos: crate::util::none_array(),
+            proof: Vec::new(),
+        }
+    }
+}
+
 /// The serialized form of a proof
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[wire_message::wire_message]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[wire_message::wire_message(type_id = "zk_circuits::SnarkWitness")]
 pub enum SnarkWitness {
     V1(SnarkWitnessV1),
 }
 
-#[derive(Debug, Clone, BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
 pub struct SnarkWitnessV1 {
     pub instances: Vec<Vec<Element>>,
     #[serde(
@@ -190,21 +242,6 @@ pub struct SnarkWitnessV1 {
     pub proof: Vec<u8>,
 }
 
-impl wire_message::WireMessage for SnarkWitness {
-    type Ctx = ();
-    type Err = core::convert::Infallible;
-
-    fn version(&self) -> u64 {
-        match self {
-            Self::V1(_) => 1,
-        }
-    }
-
-    fn upgrade_once(self, _ctx: &mut Self::Ctx) -> Result<Self, wire_message::Error> {
-        Err(Self::max_version_error())
-    }
-}
-
 #[derive(Clone, Default, Debug)]
 pub struct Signature {
     /// Secret key for the address, required to spend a note
@@ -242,3 +279,6 @@ impl<const AGG_N: usize> Default for AggregateAgg<AGG_N> {
         Self::new(core::array::from_fn(|_| aggregate_utxo.clone()))
     }
 }
+
+#[cfg(test)]
+mod tests;
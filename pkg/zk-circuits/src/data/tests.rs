@@ -0,0 +1,43 @@
+use wire_message::{test_api::Golden, VersionedJson};
+
+use super::*;
+
+#[test]
+fn snark_witness_fixtures() {
+    Golden::new(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/fixtures/snark_witness"
+    ))
+    .sample(
+        "empty",
+        SnarkWitness::V1(SnarkWitnessV1 {
+            instances: vec![],
+            proof: vec![],
+        }),
+    )
+    .sample(
+        "instances_and_proof",
+        SnarkWitness::V1(SnarkWitnessV1 {
+            instances: vec![
+                vec![Element::new(1), Element::new(2)],
+                vec![Element::MODULUS - 1u64],
+            ],
+            proof: vec![1, 2, 3, 4],
+        }),
+    )
+    .check(&mut ());
+}
+
+#[test]
+fn snark_witness_versioned_json() {
+    let witness = SnarkWitness::V1(SnarkWitnessV1 {
+        instances: vec![vec![Element::new(1)]],
+        proof: vec![1, 2, 3],
+    });
+
+    let value = witness.to_json_value().unwrap();
+    assert_eq!(value["version"], 1);
+
+    let witness_again = SnarkWitness::from_json_value(value, &mut ()).unwrap();
+    assert_eq!(witness_again, witness);
+}
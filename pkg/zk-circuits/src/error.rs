@@ -1,10 +1,45 @@
-use std::fmt::Debug;
+use std::{
+    any::Any,
+    fmt::Debug,
+    panic::{catch_unwind, AssertUnwindSafe},
+};
+
+use halo2_base::halo2_proofs::plonk;
+
+use crate::store;
+
+/// The kind of an [`Error`], which callers can match on
+///
+/// With `#[repr(u8)]`, a kind is represented as a single `u8` holding its discriminant, which
+/// counts up from 0 in declaration order, so it can be passed across FFI as that byte. New kinds
+/// are added at the end so existing discriminants don't change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u8)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The proof is malformed, or doesn't verify
+    InvalidProof,
+    /// The number of instances (public inputs) doesn't match the circuit
+    WrongInstanceCount,
+    /// The witness couldn't be synthesized, or doesn't fit in the circuit
+    Synthesis,
+    /// A proving or verifying key is invalid, or doesn't match the circuit
+    KeyMismatch,
+    /// The inputs to a circuit are invalid (e.g. the wrong number of notes)
+    InvalidInput,
+    /// An unexpected panic, caught at the crate boundary
+    Panic,
+    /// Any other error
+    Other,
+}
 
 /// An error produced by zk-circuits
 ///
 /// It is designed to be FFI-safe
 #[derive(Debug)]
 pub struct Error {
+    /// The kind of error
+    kind: ErrorKind,
     /// The debug representation of the underlying error
     debug: String,
     /// The type name of the error that caused this error
@@ -21,23 +56,34 @@ pub struct Error {
 pub type Result<T, E = Error> = core::result::Result<T, E>;
 
 impl Error {
-    pub(crate) fn err<T: Debug>(inner: T) -> Self {
+    pub(crate) fn new<T: Debug>(kind: ErrorKind, inner: T) -> Self {
         Self {
+            kind,
             debug: format!("{inner:?}"),
             type_name: core::any::type_name::<T>().to_string(),
             was_panic: false,
         }
     }
 
-    #[allow(unused)]
+    pub(crate) fn err<T: Debug>(inner: T) -> Self {
+        Self::new(ErrorKind::Other, inner)
+    }
+
     pub(crate) fn panic<T: Debug>(inner: T) -> Self {
         Self {
+            kind: ErrorKind::Panic,
             debug: format!("{inner:?}"),
             type_name: core::any::type_name::<T>().to_string(),
             was_panic: true,
         }
     }
 
+    /// The kind of this error
+    #[inline]
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
     /// Was this error caused by a panic (if false, it was caused by a `Result::Err`)
     #[inline]
     pub fn was_panic(&self) -> bool {
@@ -61,9 +107,110 @@ impl Error {
     }
 }
 
+impl From<plonk::Error> for Error {
+    fn from(err: plonk::Error) -> Self {
+        let kind = match err {
+            plonk::Error::InvalidInstances | plonk::Error::InstanceTooLarge => {
+                ErrorKind::WrongInstanceCount
+            }
+            plonk::Error::ConstraintSystemFailure
+            | plonk::Error::Opening
+            | plonk::Error::Transcript(_) => ErrorKind::InvalidProof,
+            _ => ErrorKind::Synthesis,
+        };
+
+        Self::new(kind, err)
+    }
+}
+
+impl From<store::Error> for Error {
+    fn from(err: store::Error) -> Self {
+        let kind = match err {
+            store::Error::Checksum { .. } | store::Error::NotInManifest { .. } => {
+                ErrorKind::KeyMismatch
+            }
+            _ => ErrorKind::Other,
+        };
+
+        Self::new(kind, err)
+    }
+}
+
 impl std::error::Error for Error {}
 impl core::fmt::Display for Error {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        write!(f, "Error(type = {})", self.type_name)
+        write!(
+            f,
+            "Error(kind = {:?}, type = {})",
+            self.kind, self.type_name
+        )
+    }
+}
+
+/// Run `f`, converting a panic into an [`Error`] with [`ErrorKind::Panic`]
+///
+/// This should wrap every public entry point that calls into the proving system, since it can
+/// panic on malformed inputs. Note that this has no effect if the binary is built with
+/// `panic = "abort"`.
+pub(crate) fn catch_panic<T>(f: impl FnOnce() -> Result<T>) -> Result<T> {
+    catch_unwind(AssertUnwindSafe(f))
+        .unwrap_or_else(|payload| Err(Error::panic(message(&*payload))))
+}
+
+/// The message of a panic payload, which is usually a `&str` or `String`
+fn message(payload: &(dyn Any + Send)) -> &str {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message
+    } else {
+        "unknown panic"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panics_are_caught() {
+        let err = catch_panic::<()>(|| panic!("bad proof {}", 1)).unwrap_err();
+
+        assert_eq!(err.kind(), ErrorKind::Panic);
+        assert!(err.was_panic());
+        assert_eq!(err.debug_repr(), "\"bad proof 1\"");
+
+        assert_eq!(catch_panic(|| Ok(1)).unwrap(), 1);
+    }
+
+    #[test]
+    fn plonk_errors_have_kinds() {
+        assert_eq!(
+            Error::from(plonk::Error::InvalidInstances).kind(),
+            ErrorKind::WrongInstanceCount
+        );
+        assert_eq!(
+            Error::from(plonk::Error::Opening).kind(),
+            ErrorKind::InvalidProof
+        );
+        assert_eq!(
+            Error::from(plonk::Error::Synthesis).kind(),
+            ErrorKind::Synthesis
+        );
+    }
+
+    #[test]
+    fn store_errors_have_kinds() {
+        assert_eq!(
+            Error::from(store::Error::NotInManifest {
+                path: "utxo.pk".into()
+            })
+            .kind(),
+            ErrorKind::KeyMismatch
+        );
+        assert_eq!(
+            Error::from(store::Error::NotFound("kzg_bn254_21.srs".to_owned())).kind(),
+            ErrorKind::Other
+        );
     }
 }
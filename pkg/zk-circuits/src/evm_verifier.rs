@@ -1,4 +1,4 @@
-use std::rc::Rc;
+use std::{fs, path::Path, rc::Rc};
 
 use halo2_base::halo2_proofs::{
     halo2curves::bn256::{self, Bn256, G1Affine},
@@ -13,16 +13,23 @@ use halo2_base::halo2_proofs::{
         VerificationStrategy,
     },
     transcript::TranscriptReadBuffer,
+    SerdeFormat,
 };
 use rand::rngs::OsRng;
 use snark_verifier::{
-    loader::evm::EvmLoader,
+    loader::evm::{compile_yul, deploy_and_call, encode_calldata, EvmLoader},
     pcs::kzg::{Bdfg21, Kzg},
     system::halo2::transcript::evm::EvmTranscript,
     verifier::{Plonk, PlonkVerifier},
 };
 
-use crate::{data::ParameterSet, params::load_params, CircuitKind};
+use crate::{
+    data::ParameterSet,
+    error::{catch_panic, ErrorKind},
+    params::load_params,
+    store::{self, Manifest},
+    CircuitKind,
+};
 
 pub type Error = halo2_base::halo2_proofs::plonk::Error;
 
@@ -32,38 +39,54 @@ pub fn gen_proof<C: Circuit<bn256::Fr>>(
     circuit: C,
     instances: &[&[bn256::Fr]],
 ) -> Result<Vec<u8>, crate::Error> {
-    let params = load_params(params);
+    catch_panic(|| {
+        let params = load_params(params)?;
 
-    let mut transcript: EvmTranscript<_, _, _, _> =
-        halo2_base::halo2_proofs::transcript::TranscriptWriterBuffer::<_, G1Affine, _>::init(
-            Vec::new(),
-        );
-    create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<Bn256>, _, _, _, _>(
-        params,
-        pk,
-        &[circuit],
-        &[instances],
-        OsRng,
-        &mut transcript,
-    )
-    .map_err(crate::Error::err)?;
-
-    Ok(transcript.finalize())
+        let mut transcript: EvmTranscript<_, _, _, _> =
+            halo2_base::halo2_proofs::transcript::TranscriptWriterBuffer::<_, G1Affine, _>::init(
+                Vec::new(),
+            );
+        create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<Bn256>, _, _, _, _>(
+            params,
+            pk,
+            &[circuit],
+            &[instances],
+            OsRng,
+            &mut transcript,
+        )?;
+
+        Ok(transcript.finalize())
+    })
 }
 
+/// Verify a proof made with [`gen_proof`]
+///
+/// Returns an [`ErrorKind::InvalidProof`] error if the proof is malformed or doesn't verify
 pub fn verify_proof(
     kind: CircuitKind,
     proof: &[u8],
     instances: &[Vec<bn256::Fr>],
-) -> Result<bool, Error> {
-    let params = load_params(kind.params());
-    let vk = kind.vk();
+) -> Result<(), crate::Error> {
+    let vk = kind.vk()?;
+
+    if instances.len() != vk.cs().num_instance_columns() {
+        return Err(crate::Error::new(
+            ErrorKind::WrongInstanceCount,
+            format!(
+                "expected {} instance columns, got {}",
+                vk.cs().num_instance_columns(),
+                instances.len()
+            ),
+        ));
+    }
+
+    catch_panic(|| {
+        let params = load_params(kind.params())?;
 
-    let mut transcript: EvmTranscript<_, _, _, _> =
-        TranscriptReadBuffer::<_, G1Affine, _>::init(proof);
+        let mut transcript: EvmTranscript<_, _, _, _> =
+            TranscriptReadBuffer::<_, G1Affine, _>::init(proof);
 
-    Ok(VerificationStrategy::<_, VerifierSHPLONK<_>>::finalize(
-        halo2_base::halo2_proofs::plonk::verify_proof::<
+        let accumulator = halo2_base::halo2_proofs::plonk::verify_proof::<
             _,
             VerifierSHPLONK<_>,
             _,
@@ -75,33 +98,183 @@ pub fn verify_proof(
             AccumulatorStrategy::new(params.verifier_params()),
             &[&instances.iter().map(|a| a.as_slice()).collect::<Vec<_>>()],
             &mut transcript,
-        )?,
-    ))
+        )?;
+
+        if !VerificationStrategy::<_, VerifierSHPLONK<_>>::finalize(accumulator) {
+            return Err(crate::Error::new(
+                ErrorKind::InvalidProof,
+                "final pairing check failed",
+            ));
+        }
+
+        Ok(())
+    })
 }
 
 pub fn generate_verifier(
     params: ParameterSet,
     pk: &ProvingKey<bn256::G1Affine>,
     num_instance: Vec<usize>,
-) -> String {
-    let params = load_params(params);
-    let vk = pk.get_vk();
-
-    let svk: snark_verifier::pcs::kzg::KzgSuccinctVerifyingKey<G1Affine> = params.get_g()[0].into();
-    let dk: snark_verifier::pcs::kzg::KzgDecidingKey<Bn256> = (params.g2(), params.s_g2()).into();
-    let protocol = snark_verifier::system::halo2::compile(
-        params,
-        vk,
-        snark_verifier::system::halo2::Config::kzg().with_num_instance(num_instance.clone()),
-    );
-    let loader: Rc<EvmLoader> = EvmLoader::new::<bn256::Fq, bn256::Fr>();
-    let protocol = protocol.loaded(&loader);
-
-    let mut transcript = EvmTranscript::<G1Affine, Rc<EvmLoader>, _, _>::new(&loader);
-    let instances = transcript.load_instances(num_instance);
-    let proof =
-        Plonk::<Kzg<Bn256, Bdfg21>>::read_proof(&svk, &protocol, &instances, &mut transcript);
-    Plonk::<Kzg<Bn256, Bdfg21>>::verify(&svk, &dk, &protocol, &instances, &proof);
-
-    loader.yul_code()
+) -> crate::Result<String> {
+    catch_panic(|| {
+        let params = load_params(params)?;
+        let vk = pk.get_vk();
+
+        let svk: snark_verifier::pcs::kzg::KzgSuccinctVerifyingKey<G1Affine> =
+            params.get_g()[0].into();
+        let dk: snark_verifier::pcs::kzg::KzgDecidingKey<Bn256> =
+            (params.g2(), params.s_g2()).into();
+        let protocol = snark_verifier::system::halo2::compile(
+            params,
+            vk,
+            snark_verifier::system::halo2::Config::kzg().with_num_instance(num_instance.clone()),
+        );
+        let loader: Rc<EvmLoader> = EvmLoader::new::<bn256::Fq, bn256::Fr>();
+        let protocol = protocol.loaded(&loader);
+
+        let mut transcript = EvmTranscript::<G1Affine, Rc<EvmLoader>, _, _>::new(&loader);
+        let instances = transcript.load_instances(num_instance);
+        let proof =
+            Plonk::<Kzg<Bn256, Bdfg21>>::read_proof(&svk, &protocol, &instances, &mut transcript);
+        Plonk::<Kzg<Bn256, Bdfg21>>::verify(&svk, &dk, &protocol, &instances, &proof);
+
+        Ok(loader.yul_code())
+    })
+}
+
+/// The EVM verifier contract for a [`CircuitKind`]
+#[derive(Debug, Clone)]
+pub struct EvmVerifier {
+    pub kind: CircuitKind,
+    /// The Yul code of the verifier contract
+    pub yul: String,
+    /// The sha256 of the verifying key that the verifier was generated from
+    pub vk_hash: String,
+}
+
+impl EvmVerifier {
+    /// Generate the verifier from the proving key for `kind`
+    pub fn generate(kind: CircuitKind) -> crate::Result<Self> {
+        let pk = kind.pk()?;
+        let yul = generate_verifier(kind.params(), pk, kind.num_instance())?;
+        let vk_hash = store::sha256_hex(&pk.get_vk().to_bytes(SerdeFormat::Processed));
+
+        Ok(Self { kind, yul, vk_hash })
+    }
+
+    /// The name of the Yul file, e.g. `burn_verifier.yul`
+    pub fn file_name(&self) -> String {
+        format!("{}_verifier.yul", self.kind.name())
+    }
+
+    /// Compile the Yul code to deployment bytecode
+    ///
+    /// This requires `solc` to be on the `PATH`
+    pub fn compile(&self) -> crate::Result<Vec<u8>> {
+        catch_panic(|| Ok(compile_yul(&self.yul)))
+    }
+
+    /// Deploy `deployment_code` (e.g. from [`EvmVerifier::compile`]) to an in-memory EVM, and call
+    /// it with a fresh proof of the default circuit for this kind, returning the gas used
+    pub fn check(&self, deployment_code: Vec<u8>) -> crate::Result<u64> {
+        let (instances, proof) = self.kind.default_evm_proof()?;
+
+        evm_verify(deployment_code, &[instances], &proof)
+    }
+}
+
+/// Deploy `deployment_code` to an in-memory EVM, and call it with `instances` and `proof`,
+/// returning the gas used
+///
+/// Returns an [`ErrorKind::InvalidProof`] error if the call reverts
+pub fn evm_verify(
+    deployment_code: Vec<u8>,
+    instances: &[Vec<bn256::Fr>],
+    proof: &[u8],
+) -> Result<u64, crate::Error> {
+    let calldata = encode_calldata(instances, proof);
+
+    catch_panic(|| {
+        deploy_and_call(deployment_code, calldata)
+            .map_err(|err| crate::Error::new(ErrorKind::InvalidProof, err))
+    })
+}
+
+/// A verifier contract that is deployed on Ethereum, compiled from the Yul checked in next to its
+/// circuit
+///
+/// The bytecode is read from `eth/contracts`, so this checks the exact bytecode that is deployed,
+/// without a hardhat node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DeployedVerifier {
+    /// Verifies `AggregateAgg<1>` proofs, see `aggregate_agg/aggregate_verifier.yul`
+    Aggregate,
+    /// Verifies [`CircuitKind::Mint`] proofs
+    Mint,
+    /// Verifies [`CircuitKind::Burn`] proofs
+    Burn,
+}
+
+impl DeployedVerifier {
+    pub const ALL: [Self; 3] = [Self::Aggregate, Self::Mint, Self::Burn];
+
+    /// The name of the bytecode file in `eth/contracts`
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            Self::Aggregate => "AggregateVerifier.bin",
+            Self::Mint => "MintVerifier.bin",
+            Self::Burn => "BurnVerifier.bin",
+        }
+    }
+
+    /// The deployment bytecode of the verifier
+    pub fn bytecode(&self) -> Vec<u8> {
+        let hex = match self {
+            Self::Aggregate => include_str!("../../../eth/contracts/AggregateVerifier.bin"),
+            Self::Mint => include_str!("../../../eth/contracts/MintVerifier.bin"),
+            Self::Burn => include_str!("../../../eth/contracts/BurnVerifier.bin"),
+        };
+
+        hex::decode(hex.trim()).expect("verifier bytecode is hex")
+    }
+
+    /// Deploy the verifier to an in-memory EVM, and call it with `instances` and `proof` (from
+    /// [`gen_proof`]), returning the gas used
+    ///
+    /// Returns an [`ErrorKind::InvalidProof`] error if the call reverts
+    pub fn verify(&self, instances: &[Vec<bn256::Fr>], proof: &[u8]) -> crate::Result<u64> {
+        evm_verify(self.bytecode(), instances, proof)
+    }
+}
+
+/// Generate the verifiers for `kinds`, and write them to `dir` with a manifest of the verifying key
+/// hashes they were generated from
+pub fn write_verifiers(dir: &Path, kinds: &[CircuitKind]) -> crate::Result<Vec<EvmVerifier>> {
+    let io_err = |path: &Path| {
+        let path = path.to_owned();
+        move |source| store::Error::Io { path, source }
+    };
+
+    fs::create_dir_all(dir).map_err(io_err(dir))?;
+
+    let mut verifiers = vec![];
+    let mut manifest = Manifest::new();
+
+    for kind in kinds {
+        let verifier = EvmVerifier::generate(*kind)?;
+
+        let path = dir.join(verifier.file_name());
+        fs::write(&path, &verifier.yul).map_err(io_err(&path))?;
+
+        manifest.insert(
+            store::verifying_key_file_name(*kind),
+            verifier.vk_hash.clone(),
+        );
+        verifiers.push(verifier);
+    }
+
+    let manifest_path = dir.join(store::MANIFEST_FILE_NAME);
+    fs::write(&manifest_path, store::to_json(&manifest)).map_err(io_err(&manifest_path))?;
+
+    Ok(verifiers)
 }
@@ -163,15 +163,11 @@ impl<const INSERTS: usize, const MERKLE_D: usize> Batch<INSERTS, MERKLE_D> {
         vec![old_root, new_root].into_iter().chain(leafs).collect()
     }
 
-    pub fn snark(&self, params: ParameterSet) -> Result<Snark, Error> {
+    pub fn snark(&self, params: ParameterSet) -> crate::Result<Snark> {
+        let kzg_params = load_params(params)?;
         let (pk, _) = self.keygen(params);
 
-        Snark::create(
-            Self::default(),
-            vec![self.public_inputs()],
-            load_params(params),
-            &pk,
-        )
+        Snark::create(Self::default(), vec![self.public_inputs()], kzg_params, &pk)
     }
 
     pub fn keygen(&self, params: ParameterSet) -> (ProvingKey<G1Affine>, VerifyingKey<G1Affine>) {
@@ -183,7 +179,7 @@ impl<const INSERTS: usize, const MERKLE_D: usize> Batch<INSERTS, MERKLE_D> {
         params: &ParamsKZG<Bn256>,
         pk: &ProvingKey<G1Affine>,
         rng: impl RngCore,
-    ) -> Result<Proof, Error> {
+    ) -> crate::Result<Proof> {
         let circuit = Self::default();
         let instance = self.public_inputs();
         let instances = &[instance.as_slice()];
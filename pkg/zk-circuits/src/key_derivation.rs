@@ -0,0 +1,328 @@
+//! Key management for note owners
+//!
+//! All of a wallet's keys are derived from a single [`Seed`]:
+//! ```text
+//! Seed
+//!  └─ SpendingKey
+//!      ├─ AddressKey (index 0, 1, 2, ...)      - spends and nullifies notes sent to its address
+//!      └─ FullViewingKey
+//!          └─ ViewingKey (index 0, 1, 2, ...)  - decrypts notes sent to its address
+//! ```
+//!
+//! Each [`AddressKey`] is the `secret_key` used by the existing circuits, so its address is
+//! `hash_merge([secret_key, 0])` (as in [`Points`][crate::data::Points]) and nullifiers are
+//! [`Note::nullifier`] with that secret key. A wallet can hand out a fresh
+//! [`PaymentAddress`] per counterparty, and share its [`FullViewingKey`] to give view access
+//! without spend authority.
+//!
+//! Each payment address has its own public viewing key, so the payment addresses of a wallet
+//! can't be linked to each other. A [`FullViewingKey`] derives the viewing key of every index, so
+//! it finds notes by trial decryption over the indices that have been handed out.
+//!
+//! The circuits derive an address and its nullifiers from the same secret key, so a viewing key
+//! can see incoming notes, but can't compute their nullifiers to see when they are spent.
+
+use crate::{
+    data::Note,
+    note_encryption::{self, NoteCiphertext, PublicViewingKey, ViewingKey},
+    util::blake_hash,
+};
+use rand::{CryptoRng, RngCore};
+use smirk::hash_merge;
+use zk_primitives::Element;
+
+/// Domain separator for deriving a [`SpendingKey`] from a [`Seed`]
+const SPENDING_KEY_DOMAIN: &[u8] = b"spending key";
+
+/// Domain separator for deriving a [`FullViewingKey`] from a [`SpendingKey`]
+const VIEWING_KEY_DOMAIN: &[u8] = b"viewing key";
+
+/// Domain separator for deriving the [`ViewingKey`] of an address from a [`FullViewingKey`]
+const ADDRESS_VIEWING_KEY_DOMAIN: &[u8] = b"address viewing key";
+
+/// The root secret of a wallet, which every other key is derived from
+#[derive(Clone, PartialEq, Eq)]
+pub struct Seed([u8; 32]);
+
+impl std::fmt::Debug for Seed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Seed(..)")
+    }
+}
+
+impl Seed {
+    pub fn random(rng: &mut (impl RngCore + CryptoRng)) -> Self {
+        let mut bytes = [0; 32];
+        rng.fill_bytes(&mut bytes);
+        Self(bytes)
+    }
+
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    pub fn spending_key(&self) -> SpendingKey {
+        SpendingKey(blake_hash([SPENDING_KEY_DOMAIN, &self.0]))
+    }
+}
+
+/// The key that all address keys and the viewing key are derived from
+///
+/// Anyone with this key can spend every note sent to the wallet
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct SpendingKey(Element);
+
+impl std::fmt::Debug for SpendingKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SpendingKey(..)")
+    }
+}
+
+impl SpendingKey {
+    /// The key for the diversified address at `index`
+    pub fn address_key(&self, index: u64) -> AddressKey {
+        AddressKey {
+            index,
+            secret_key: hash_merge([self.0, Element::from(index)]),
+        }
+    }
+
+    pub fn full_viewing_key(&self) -> FullViewingKey {
+        FullViewingKey(blake_hash([VIEWING_KEY_DOMAIN, &self.0.to_be_bytes()]))
+    }
+
+    /// The address at `index`, with the public viewing key that notes to it are encrypted to
+    pub fn payment_address(&self, index: u64) -> PaymentAddress {
+        PaymentAddress {
+            address: self.address_key(index).address(),
+            viewing_key: self.full_viewing_key().public_key(index),
+        }
+    }
+}
+
+/// The secret key of a single diversified address
+///
+/// This is the `secret_key` of an [`InputNote`][crate::data::InputNote] when spending notes sent
+/// to [`AddressKey::address`]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct AddressKey {
+    index: u64,
+    secret_key: Element,
+}
+
+impl std::fmt::Debug for AddressKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AddressKey")
+            .field("index", &self.index)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AddressKey {
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    pub fn secret_key(&self) -> Element {
+        self.secret_key
+    }
+
+    /// The address that notes are sent to, which is checked against the secret key in the
+    /// circuits
+    pub fn address(&self) -> Element {
+        hash_merge([self.secret_key, Element::ZERO])
+    }
+
+    /// The nullifier of `note` when spent with this key
+    pub fn nullifier(&self, note: &Note) -> Element {
+        note.nullifier(self.secret_key)
+    }
+}
+
+/// A key that can read every note sent to the wallet's addresses, but can't spend them
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct FullViewingKey(Element);
+
+impl std::fmt::Debug for FullViewingKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("FullViewingKey(..)")
+    }
+}
+
+impl FullViewingKey {
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(Element::from_be_bytes(bytes))
+    }
+
+    pub fn to_bytes(self) -> [u8; 32] {
+        self.0.to_be_bytes()
+    }
+
+    /// The key that notes sent to the address at `index` are decrypted with
+    pub fn viewing_key(&self, index: u64) -> ViewingKey {
+        let key = blake_hash([
+            ADDRESS_VIEWING_KEY_DOMAIN,
+            &self.0.to_be_bytes(),
+            &index.to_be_bytes(),
+        ]);
+
+        // Field elements are less than the secp256k1 group order, so this only fails for zero
+        ViewingKey::from_bytes(key.to_be_bytes()).expect("viewing key derivation produced zero")
+    }
+
+    /// The key that notes sent to the address at `index` are encrypted to
+    pub fn public_key(&self, index: u64) -> PublicViewingKey {
+        self.viewing_key(index).public_key()
+    }
+
+    /// Decrypt a note sent to the address at `index`, see [`note_encryption::try_decrypt`]
+    pub fn try_decrypt(&self, index: u64, ciphertext: &NoteCiphertext) -> Option<Note> {
+        note_encryption::try_decrypt(&self.viewing_key(index), ciphertext)
+    }
+}
+
+/// Everything a sender needs to send a note to a wallet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PaymentAddress {
+    /// The address of the notes
+    pub address: Element,
+    /// The key that the notes are encrypted to
+    pub viewing_key: PublicViewingKey,
+}
+
+impl PaymentAddress {
+    /// Create a note of `value` USDC for this address, and encrypt it to the viewing key
+    pub fn new_note(
+        &self,
+        value: Element,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> (Note, NoteCiphertext) {
+        let note = Note::new(self.address, value);
+        let ciphertext = note_encryption::encrypt(&note, &self.viewing_key, rng);
+        (note, ciphertext)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chips::poseidon::poseidon_hash,
+        constants::MERKLE_TREE_DEPTH,
+        data::{InputNote, Utxo, UtxoKind},
+        test::rollup::{Rollup, Wallet, WalletNote},
+    };
+    use halo2_base::halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr};
+    use rand::thread_rng;
+
+    #[test]
+    fn derivation_is_deterministic() {
+        let seed = Seed::random(&mut thread_rng());
+        let copy = Seed::from_bytes(*seed.as_bytes());
+
+        assert_eq!(seed.spending_key(), copy.spending_key());
+        assert_eq!(
+            seed.spending_key().payment_address(3),
+            copy.spending_key().payment_address(3)
+        );
+
+        let full_viewing_key = seed.spending_key().full_viewing_key();
+        assert_eq!(
+            FullViewingKey::from_bytes(full_viewing_key.to_bytes()),
+            full_viewing_key
+        );
+    }
+
+    #[test]
+    fn addresses_are_diversified() {
+        let spending_key = Seed::random(&mut thread_rng()).spending_key();
+
+        let first = spending_key.address_key(0);
+        let second = spending_key.address_key(1);
+
+        assert_ne!(first.secret_key(), second.secret_key());
+        assert_ne!(first.address(), second.address());
+        assert_eq!(second.index(), 1);
+
+        // Payment addresses don't share a viewing key, so they can't be linked
+        assert_ne!(
+            spending_key.payment_address(0).viewing_key,
+            spending_key.payment_address(1).viewing_key
+        );
+    }
+
+    #[test]
+    fn address_matches_circuits() {
+        let address_key = Seed::random(&mut thread_rng())
+            .spending_key()
+            .address_key(0);
+
+        let wallet = Wallet {
+            pk: address_key.secret_key(),
+        };
+
+        assert_eq!(Fr::from(address_key.address()), wallet.address());
+        assert_eq!(
+            Fr::from(address_key.address()),
+            poseidon_hash([address_key.secret_key().into(), Fr::zero()])
+        );
+    }
+
+    #[test]
+    fn full_viewing_key_decrypts_notes() {
+        let spending_key = Seed::random(&mut thread_rng()).spending_key();
+        let full_viewing_key = spending_key.full_viewing_key();
+
+        let payment_address = spending_key.payment_address(7);
+        let (note, ciphertext) = payment_address.new_note(Element::from(10u64), &mut thread_rng());
+
+        assert_eq!(note.address(), spending_key.address_key(7).address());
+        assert_eq!(full_viewing_key.try_decrypt(7, &ciphertext), Some(note));
+        assert_eq!(full_viewing_key.try_decrypt(6, &ciphertext), None);
+
+        let other = Seed::random(&mut thread_rng())
+            .spending_key()
+            .full_viewing_key();
+        assert_eq!(other.try_decrypt(7, &ciphertext), None);
+    }
+
+    #[test]
+    fn address_key_spends_in_utxo() {
+        let k = 14;
+        let address_key = Seed::random(&mut thread_rng())
+            .spending_key()
+            .address_key(2);
+
+        let mut rollup = Rollup::new();
+        let recipient = rollup.new_wallet();
+
+        let note = Note::new(address_key.address(), Element::from(10u64));
+        rollup.tree.insert(note.commitment(), ()).unwrap();
+
+        let wallet = Wallet {
+            pk: address_key.secret_key(),
+        };
+        let wallet_note = WalletNote::new(wallet, note.clone());
+        assert_eq!(wallet_note.nullifier(), address_key.nullifier(&note));
+
+        let input_notes = [
+            rollup.to_input_note(&wallet_note),
+            InputNote::padding_note(),
+        ];
+        let output_notes = [recipient.new_note(10), Note::padding_note()];
+
+        let circuit = Utxo::<MERKLE_TREE_DEPTH>::new(
+            input_notes,
+            output_notes,
+            rollup.root_hash(),
+            UtxoKind::Transfer,
+        );
+
+        let prover = MockProver::<Fr>::run(k, &circuit, vec![circuit.public_inputs()]).unwrap();
+        prover.assert_satisfied();
+    }
+}
@@ -1,40 +1,86 @@
 use std::sync::OnceLock;
 
 use halo2_base::halo2_proofs::{
-    halo2curves::bn256::G1Affine,
-    plonk::{ProvingKey, VerifyingKey},
+    halo2curves::bn256::{Fr, G1Affine},
+    plonk::{Circuit, ProvingKey, VerifyingKey},
+    SerdeFormat,
 };
 
 use crate::{
     aggregate_utxo::AggregateUtxo,
+    chips::aggregation::aggregate::AggregationChip,
+    compliance::Compliance,
     data::{AggregateAgg, Burn, Mint, ParameterSet, Points, Signature, Utxo},
+    error::{catch_panic, Error, ErrorKind},
+    evm_verifier,
+    params::load_params,
+    store::{self, Manifest, Store},
 };
 
 type VK = VerifyingKey<G1Affine>;
 type PK = ProvingKey<G1Affine>;
 
+/// The sha256 of the verifying key of each circuit, written by the `keygen` binary
+///
+/// Regenerate with `UPDATE_EXPECT=1 cargo test keys::tests::pinned_verifying_keys --release`
+const PINNED_VK_MANIFEST: &str = include_str!("vk/manifest.json");
+
 // macro because we don't have a trait for this, just a convention
 macro_rules! create {
+    ($self:expr, $circuit:ty) => {{
+        match read_keys::<$circuit>(store::global(), *$self)? {
+            Some(keys) => keys,
+            None => {
+                // Load the params first, so that missing params are an error rather than a panic
+                // in keygen
+                load_params($self.params())?;
+                $self.keygen()
+            }
+        }
+    }};
+}
+
+macro_rules! generate {
     ($self:expr, $circuit:ty) => {{
         let circ = <$circuit>::default();
         circ.keygen($self.params())
     }};
 }
 
+macro_rules! num_instance {
+    ($circuit:ty) => {{
+        vec![<$circuit>::default().public_inputs().len()]
+    }};
+}
+
+macro_rules! evm_proof {
+    ($self:expr, $circuit:ty) => {{
+        let circ = <$circuit>::default();
+        let instances = circ.public_inputs();
+        let proof = evm_verifier::gen_proof($self.params(), $self.pk()?, circ, &[&instances])?;
+        (instances, proof)
+    }};
+}
+
 macro_rules! vk_function {
     ($name:ident, $t:ty) => {
-        fn $name() -> &'static VerifyingKey<G1Affine> {
-            static CACHE: OnceLock<VerifyingKey<G1Affine>> = OnceLock::new();
+        fn $name() -> crate::Result<&'static VerifyingKey<G1Affine>> {
+            static CACHE: OnceLock<Option<VerifyingKey<G1Affine>>> = OnceLock::new();
             const VK_HEX: &str = include_str!(concat!("vk/", stringify!($name)));
 
-            CACHE.get_or_init(|| {
-                let vk_bytes = hex::decode(VK_HEX.replace(['\n', '"', ' '], "")).unwrap();
-                VerifyingKey::<G1Affine>::from_bytes::<$t>(
-                    &vk_bytes,
-                    halo2_base::halo2_proofs::SerdeFormat::Processed,
-                )
-                .unwrap()
-            })
+            CACHE
+                .get_or_init(|| {
+                    let vk_bytes = hex::decode(VK_HEX.replace(['\n', '"', ' '], "")).ok()?;
+                    VerifyingKey::<G1Affine>::from_bytes::<$t>(&vk_bytes, SerdeFormat::Processed)
+                        .ok()
+                })
+                .as_ref()
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::KeyMismatch,
+                        concat!("invalid embedded verifying key ", stringify!($name)),
+                    )
+                })
         }
     };
 }
@@ -44,32 +90,88 @@ vk_function!(points, Points);
 vk_function!(utxo, Utxo::<161>);
 vk_function!(utxo_agg_3_161_12, AggregateUtxo::<3, 161, 12>);
 
-
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum CircuitKind {
     Signature,
     Points,
+    /// A UTXO with 2 inputs and 2 outputs
     Utxo,
+    /// A UTXO with 4 inputs and 2 outputs
+    Utxo4x2,
+    /// A UTXO with 8 inputs and 2 outputs
+    Utxo8x2,
+    /// An aggregation of [`CircuitKind::Utxo`] proofs
     AggUtxo,
     AggAgg,
     Burn,
     Mint,
+    Compliance,
 }
 
 impl CircuitKind {
+    /// The kind of a UTXO with `inputs` inputs and `outputs` outputs, or `None` if that shape is
+    /// not in [`UTXO_SHAPES`][crate::constants::UTXO_SHAPES]
+    pub fn utxo(inputs: usize, outputs: usize) -> Option<Self> {
+        match (inputs, outputs) {
+            (2, 2) => Some(Self::Utxo),
+            (4, 2) => Some(Self::Utxo4x2),
+            (8, 2) => Some(Self::Utxo8x2),
+            _ => None,
+        }
+    }
+
+    pub const ALL: [Self; 10] = [
+        Self::Signature,
+        Self::Points,
+        Self::Utxo,
+        Self::Utxo4x2,
+        Self::Utxo8x2,
+        Self::AggUtxo,
+        Self::AggAgg,
+        Self::Burn,
+        Self::Mint,
+        Self::Compliance,
+    ];
+
+    /// The kind with [`CircuitKind::name`] `name`
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    /// A unique name for this kind, used for its key files
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Signature => "signature",
+            Self::Points => "points",
+            Self::Utxo => "utxo",
+            Self::Utxo4x2 => "utxo_4x2",
+            Self::Utxo8x2 => "utxo_8x2",
+            Self::AggUtxo => "agg_utxo",
+            Self::AggAgg => "agg_agg",
+            Self::Burn => "burn",
+            Self::Mint => "mint",
+            Self::Compliance => "compliance",
+        }
+    }
+
     #[inline]
     pub fn params(&self) -> ParameterSet {
         match self {
             Self::Points => ParameterSet::Fourteen,
             Self::Utxo => ParameterSet::Fourteen,
+            Self::Utxo4x2 => ParameterSet::Fifteen,
+            Self::Utxo8x2 => ParameterSet::Sixteen,
             Self::AggUtxo => ParameterSet::TwentyOne,
             Self::AggAgg => ParameterSet::TwentyOne,
             Self::Signature => ParameterSet::Six,
             Self::Burn => ParameterSet::Nine,
             Self::Mint => ParameterSet::Eight,
+            Self::Compliance => ParameterSet::Fourteen,
         }
     }
 
-    pub(crate) fn vk(&self) -> &'static VK {
+    /// The verifying key, which is embedded for kinds that have been deployed
+    pub(crate) fn vk(&self) -> crate::Result<&'static VK> {
         match self {
             Self::Points => points(),
             Self::AggUtxo => utxo_agg_3_161_12(),
@@ -77,56 +179,260 @@ impl CircuitKind {
             Self::Utxo => utxo(),
 
             _ => {
-                let (_, vk) = self.keys();
-                vk
+                let (_, vk) = self.keys()?;
+                Ok(vk)
             }
         }
     }
 
-    pub(crate) fn pk(&self) -> &'static PK {
-        let (pk, _) = self.keys();
-        pk
+    /// The proving key, from the [store][crate::store] if it has one, otherwise generated from
+    /// the circuit
+    pub(crate) fn pk(&self) -> crate::Result<&'static PK> {
+        let (pk, _) = self.keys()?;
+        Ok(pk)
     }
 
-    fn keys(&self) -> &'static (PK, VK) {
+    /// The sha256 of the pinned verifying key, or `None` if this kind hasn't been pinned
+    pub fn pinned_vk_hash(&self) -> Option<&'static str> {
+        static MANIFEST: OnceLock<Manifest> = OnceLock::new();
+
+        let manifest = MANIFEST.get_or_init(|| serde_json::from_str(PINNED_VK_MANIFEST).unwrap());
+        manifest
+            .get(&store::verifying_key_file_name(*self))
+            .map(String::as_str)
+    }
+
+    /// Generate the keys from the circuit, ignoring the [store][crate::store]
+    pub(crate) fn keygen(&self) -> (PK, VK) {
+        match self {
+            Self::Signature => generate!(self, Signature),
+            Self::Points => generate!(self, Points),
+            Self::Utxo => generate!(self, Utxo::<161>),
+            Self::Utxo4x2 => generate!(self, Utxo::<161, 4, 2>),
+            Self::Utxo8x2 => generate!(self, Utxo::<161, 8, 2>),
+            Self::AggUtxo => generate!(self, AggregateUtxo::<3, 161, 12>),
+            Self::AggAgg => generate!(self, AggregateAgg::<2>),
+            Self::Burn => generate!(self, Burn::<1>),
+            Self::Mint => generate!(self, Mint::<1>),
+            Self::Compliance => generate!(self, Compliance::<161>),
+        }
+    }
+
+    /// The number of instances in each instance column
+    pub(crate) fn num_instance(&self) -> Vec<usize> {
+        match self {
+            Self::Signature => num_instance!(Signature),
+            Self::Points => num_instance!(Points),
+            Self::Utxo => num_instance!(Utxo::<161>),
+            Self::Utxo4x2 => num_instance!(Utxo::<161, 4, 2>),
+            Self::Utxo8x2 => num_instance!(Utxo::<161, 8, 2>),
+            Self::AggUtxo => num_instance!(AggregateUtxo::<3, 161, 12>),
+            Self::AggAgg => {
+                // The accumulator, the old and new roots, and the UTXO values of each aggregate,
+                // which follow the accumulator and roots in the instances of an AggUtxo
+                let utxo_values = Self::AggUtxo.num_instance()[0] - 14;
+                vec![AggregationChip::num_instance()[0] + 2 + 2 * utxo_values]
+            }
+            Self::Burn => num_instance!(Burn::<1>),
+            Self::Mint => num_instance!(Mint::<1>),
+            Self::Compliance => num_instance!(Compliance::<161>),
+        }
+    }
+
+    /// Prove the default circuit with the EVM transcript, returning its instances and the proof
+    ///
+    /// This is used to check the generated EVM verifiers against a fresh proof
+    pub(crate) fn default_evm_proof(&self) -> crate::Result<(Vec<Fr>, Vec<u8>)> {
+        Ok(match self {
+            Self::Signature => evm_proof!(self, Signature),
+            Self::Points => evm_proof!(self, Points),
+            Self::Utxo => evm_proof!(self, Utxo::<161>),
+            Self::Utxo4x2 => evm_proof!(self, Utxo::<161, 4, 2>),
+            Self::Utxo8x2 => evm_proof!(self, Utxo::<161, 8, 2>),
+            Self::AggUtxo => evm_proof!(self, AggregateUtxo::<3, 161, 12>),
+            Self::AggAgg => {
+                let circ = AggregateAgg::<2>::default();
+                let instances = circ.public_inputs()?;
+                let proof =
+                    evm_verifier::gen_proof(self.params(), self.pk()?, circ, &[&instances])?;
+                (instances, proof)
+            }
+            Self::Burn => evm_proof!(self, Burn::<1>),
+            Self::Mint => evm_proof!(self, Mint::<1>),
+            Self::Compliance => evm_proof!(self, Compliance::<161>),
+        })
+    }
+
+    fn keys(&self) -> crate::Result<&'static (PK, VK)> {
         static SIGNATURE: OnceLock<(PK, VK)> = OnceLock::new();
         static POINTS: OnceLock<(PK, VK)> = OnceLock::new();
         static UTXO_KEYS: OnceLock<(PK, VK)> = OnceLock::new();
+        static UTXO_4X2_KEYS: OnceLock<(PK, VK)> = OnceLock::new();
+        static UTXO_8X2_KEYS: OnceLock<(PK, VK)> = OnceLock::new();
         static AGG_UTXO: OnceLock<(PK, VK)> = OnceLock::new();
         static AGG_AGG: OnceLock<(PK, VK)> = OnceLock::new();
         static BURN_KEYS: OnceLock<(PK, VK)> = OnceLock::new();
         static MINT: OnceLock<(PK, VK)> = OnceLock::new();
+        static COMPLIANCE: OnceLock<(PK, VK)> = OnceLock::new();
 
-        match self {
-            Self::Signature => SIGNATURE.get_or_init(|| create!(self, Signature)),
-            Self::Points => POINTS.get_or_init(|| create!(self, Points)),
-            Self::Utxo => UTXO_KEYS.get_or_init(|| create!(self, Utxo::<161>)),
-            Self::AggUtxo => AGG_UTXO.get_or_init(|| create!(self, AggregateUtxo::<3, 161, 12>)),
-            Self::AggAgg => AGG_AGG.get_or_init(|| create!(self, AggregateAgg::<2>)),
-            Self::Burn => BURN_KEYS.get_or_init(|| create!(self, Burn::<1>)),
-            Self::Mint => MINT.get_or_init(|| create!(self, Mint::<1>)),
+        let cache = match self {
+            Self::Signature => &SIGNATURE,
+            Self::Points => &POINTS,
+            Self::Utxo => &UTXO_KEYS,
+            Self::Utxo4x2 => &UTXO_4X2_KEYS,
+            Self::Utxo8x2 => &UTXO_8X2_KEYS,
+            Self::AggUtxo => &AGG_UTXO,
+            Self::AggAgg => &AGG_AGG,
+            Self::Burn => &BURN_KEYS,
+            Self::Mint => &MINT,
+            Self::Compliance => &COMPLIANCE,
+        };
+
+        if let Some(keys) = cache.get() {
+            return Ok(keys);
         }
+
+        // A failed load isn't cached, so it is retried on the next call
+        let keys = match self {
+            Self::Signature => create!(self, Signature),
+            Self::Points => create!(self, Points),
+            Self::Utxo => create!(self, Utxo::<161>),
+            Self::Utxo4x2 => create!(self, Utxo::<161, 4, 2>),
+            Self::Utxo8x2 => create!(self, Utxo::<161, 8, 2>),
+            Self::AggUtxo => create!(self, AggregateUtxo::<3, 161, 12>),
+            Self::AggAgg => create!(self, AggregateAgg::<2>),
+            Self::Burn => create!(self, Burn::<1>),
+            Self::Mint => create!(self, Mint::<1>),
+            Self::Compliance => create!(self, Compliance::<161>),
+        };
+
+        Ok(cache.get_or_init(|| keys))
     }
 }
 
+/// Read the proving key for `kind` from `store`, or `None` if the store doesn't have one
+///
+/// Returns a [`ErrorKind::KeyMismatch`] error if the key can't be read for `C`, or if its
+/// verifying key isn't the [pinned][CircuitKind::pinned_vk_hash] one
+fn read_keys<C: Circuit<Fr>>(store: &Store, kind: CircuitKind) -> crate::Result<Option<(PK, VK)>> {
+    let Some(bytes) = store.read_proving_key(kind)? else {
+        return Ok(None);
+    };
+
+    let pk = catch_panic(|| {
+        PK::from_bytes::<C>(&bytes, SerdeFormat::RawBytes).map_err(|err| {
+            Error::new(
+                ErrorKind::KeyMismatch,
+                format!("invalid {kind:?} proving key: {err}"),
+            )
+        })
+    })?;
+    let vk = pk.get_vk().clone();
+
+    if let Some(expected) = kind.pinned_vk_hash() {
+        check_vk_hash(kind, &vk, expected)?;
+    }
+
+    Ok(Some((pk, vk)))
+}
+
+fn check_vk_hash(kind: CircuitKind, vk: &VK, expected: &str) -> crate::Result<()> {
+    let actual = store::sha256_hex(&vk.to_bytes(SerdeFormat::Processed));
+
+    if actual != expected {
+        return Err(Error::new(
+            ErrorKind::KeyMismatch,
+            format!("{kind:?} proving key has verifying key {actual}, expected {expected}"),
+        ));
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn utxo_shapes_have_kinds() {
+        for (inputs, outputs) in crate::constants::UTXO_SHAPES {
+            assert!(CircuitKind::utxo(inputs, outputs).is_some());
+        }
+
+        assert!(CircuitKind::utxo(3, 2).is_none());
+    }
+
     #[test]
     fn keys() {
         let kinds = [
             CircuitKind::Signature,
             CircuitKind::Points,
             CircuitKind::Utxo,
+            CircuitKind::Utxo4x2,
+            CircuitKind::Utxo8x2,
             CircuitKind::AggUtxo,
             CircuitKind::Burn,
             CircuitKind::Mint,
+            CircuitKind::Compliance,
         ];
 
         for kind in kinds {
-            let _ = kind.vk();
-            let _ = kind.keys();
+            let _ = kind.vk().unwrap();
+            kind.keys().unwrap();
+        }
+    }
+
+    #[test]
+    fn stored_keys_are_checked() {
+        let dir = std::env::temp_dir().join("zk-circuits-store-read-keys");
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = Store::new().with_path(&dir);
+        assert!(read_keys::<Signature>(&store, CircuitKind::Signature)
+            .unwrap()
+            .is_none());
+
+        store::write_keys(&dir, &[CircuitKind::Signature]).unwrap();
+
+        let (_, vk) = read_keys::<Signature>(&store, CircuitKind::Signature)
+            .unwrap()
+            .unwrap();
+        let hash = store::sha256_hex(&vk.to_bytes(SerdeFormat::Processed));
+        check_vk_hash(CircuitKind::Signature, &vk, &hash).unwrap();
+
+        let err = check_vk_hash(CircuitKind::Signature, &vk, &"0".repeat(64)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::KeyMismatch);
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for kind in CircuitKind::ALL {
+            assert_eq!(CircuitKind::from_name(kind.name()), Some(kind));
+        }
+    }
+
+    /// Fails if a change to a circuit changes its verifying key
+    #[test]
+    fn pinned_verifying_keys() {
+        let manifest = store::verifying_key_manifest(&CircuitKind::ALL);
+
+        expect_test::expect_file!["vk/manifest.json"].assert_eq(&manifest);
+    }
+
+    #[test]
+    fn embedded_verifying_keys_are_pinned() {
+        for kind in [
+            CircuitKind::Points,
+            CircuitKind::Utxo,
+            CircuitKind::AggUtxo,
+            CircuitKind::AggAgg,
+        ] {
+            let bytes = kind.vk().unwrap().to_bytes(SerdeFormat::Processed);
+
+            assert_eq!(
+                Some(store::sha256_hex(&bytes).as_str()),
+                kind.pinned_vk_hash(),
+                "{kind:?}"
+            );
         }
     }
 }
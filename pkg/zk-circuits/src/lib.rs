@@ -8,16 +8,22 @@ mod burn;
 pub mod chips;
 pub mod compliance;
 pub mod constants;
+pub mod cost;
 pub mod evm_verifier;
 pub(crate) mod fr;
 pub mod insert;
+pub mod key_derivation;
 pub mod mint;
+pub mod note_encryption;
 pub mod points;
 pub mod proof;
 pub mod proof_format;
+pub mod proof_system;
 pub(crate) mod signature;
+pub mod store;
 pub mod util;
 mod utxo;
+pub mod wallet;
 
 mod error;
 
@@ -34,6 +40,6 @@ pub(crate) use crate::chips::aggregation::snark::Snark;
 pub use constants::{UTXO_INPUTS, UTXO_OUTPUTS};
 pub use keys::CircuitKind;
 
-pub use error::{Error, Result};
+pub use error::{Error, ErrorKind, Result};
 pub use zk_primitives::Base;
 
@@ -66,22 +66,18 @@ impl<const L: usize> Mint<L> {
         params: &ParamsKZG<Bn256>,
         pk: &ProvingKey<G1Affine>,
         rng: impl RngCore,
-    ) -> Result<Proof, Error> {
+    ) -> crate::Result<Proof> {
         let circuit = Self::default();
         let instance = self.public_inputs();
         let instances = &[instance.as_slice()];
         Proof::create(params, pk, circuit, instances, rng)
     }
 
-    pub fn snark(&self, params: ParameterSet) -> Result<Snark, Error> {
+    pub fn snark(&self, params: ParameterSet) -> crate::Result<Snark> {
+        let kzg_params = load_params(params)?;
         let (pk, _) = self.keygen(params);
 
-        Snark::create(
-            self.clone(),
-            vec![self.public_inputs()],
-            load_params(params),
-            &pk,
-        )
+        Snark::create(self.clone(), vec![self.public_inputs()], kzg_params, &pk)
     }
 
     pub fn evm_proof(&self, params: ParameterSet) -> Result<Vec<u8>, crate::Error> {
@@ -2,6 +2,7 @@ use crate::data::{Mint, Note, ParameterSet};
 use crate::evm_verifier;
 use crate::test::util::get_params;
 use crate::util::{insecure_random_element, keygen_from_params};
+use crate::CircuitKind;
 use halo2_base::halo2_proofs::dev::MockProver;
 use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
 use zk_primitives::Element;
@@ -35,8 +36,52 @@ fn generate_verifier() {
 
     let (pk, _) = keygen_from_params(params_8, &circuit);
     let yul_code =
-        evm_verifier::generate_verifier(params_8, &pk, vec![circuit.public_inputs().len()]);
+        evm_verifier::generate_verifier(params_8, &pk, vec![circuit.public_inputs().len()])
+            .unwrap();
 
     let expected_yul_code = expect_test::expect_file!["./mint_verifier.yul"];
     expected_yul_code.assert_eq(&yul_code);
 }
+
+#[test]
+fn kind_verifier_matches_checked_in() {
+    let verifier = evm_verifier::EvmVerifier::generate(CircuitKind::Mint).unwrap();
+
+    let expected_yul_code = expect_test::expect_file!["./mint_verifier.yul"];
+    expected_yul_code.assert_eq(&verifier.yul);
+}
+
+#[test]
+fn verifier_verifies_fresh_proof() {
+    let verifier = evm_verifier::EvmVerifier::generate(CircuitKind::Mint).unwrap();
+
+    // The deployed bytecode is compiled from the checked-in Yul, which
+    // `kind_verifier_matches_checked_in` compares against the generated verifier
+    let gas = verifier
+        .check(evm_verifier::DeployedVerifier::Mint.bytecode())
+        .unwrap();
+    assert!(gas > 0);
+}
+
+#[test]
+#[ignore = "requires solc"]
+fn compiled_verifier_matches_deployed() {
+    let verifier = evm_verifier::EvmVerifier::generate(CircuitKind::Mint).unwrap();
+
+    assert_eq!(
+        verifier.compile().unwrap(),
+        evm_verifier::DeployedVerifier::Mint.bytecode()
+    );
+}
+
+#[test]
+fn deployed_verifier_verifies_proof() {
+    let note = Note::new(insecure_random_element(), Element::from(100u64));
+    let mint = Mint::new([note]);
+    let proof = mint.evm_proof(ParameterSet::Eight).unwrap();
+
+    let gas = evm_verifier::DeployedVerifier::Mint
+        .verify(&[mint.public_inputs()], &proof)
+        .unwrap();
+    assert!(gas < 1_000_000, "mint verifier used {gas} gas");
+}
@@ -0,0 +1,327 @@
+//! Encryption of notes for their recipients
+//!
+//! The sender of a note encrypts it to the recipient's [`PublicViewingKey`], and publishes the
+//! [`NoteCiphertext`] alongside the note's commitment. Anyone with the matching [`ViewingKey`] can
+//! find their notes by trial decryption with [`try_decrypt`], so notes don't need to be relayed in
+//! plaintext.
+//!
+//! The scheme is ECIES over secp256k1: each note gets a fresh ephemeral key, the shared secret
+//! with the recipient's key is hashed into a ChaCha20-Poly1305 key, and the note commitment is
+//! authenticated as associated data, so a ciphertext can't be replayed against another
+//! commitment.
+
+use std::{fmt, str::FromStr};
+
+use crate::data::Note;
+use blake2b_simd::Params as Blake2bParams;
+use borsh::{BorshDeserialize, BorshSerialize};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use rand::{CryptoRng, RngCore};
+use secp256k1::{ecdh::SharedSecret, PublicKey, SecretKey, SECP256K1};
+use serde::{Deserialize, Serialize};
+use zk_primitives::{decode_bech32m, encode_bech32m, DecodeError, Element};
+
+/// Personalisation of the blake2b hash used to derive the symmetric key
+const KDF_PERSONALISATION: &[u8; 16] = b"Polybase_NoteEnc";
+
+/// The size of a serialized secp256k1 public key
+const PUBLIC_KEY_LEN: usize = 33;
+
+/// The encrypted fields of a note: address, value, psi and source
+const PLAINTEXT_LEN: usize = 4 * 32;
+
+/// A secret key that can decrypt notes sent to its [`PublicViewingKey`]
+///
+/// A viewing key can read notes, but can't spend them. It is displayed (and serialized) as a
+/// bech32m string with the prefix `payyvk`, so it can be shared to give view access
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct ViewingKey(SecretKey);
+
+impl fmt::Debug for ViewingKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ViewingKey(..)")
+    }
+}
+
+impl ViewingKey {
+    /// The human-readable prefix of the bech32m encoding of a viewing key
+    pub const HRP: &'static str = "payyvk";
+
+    pub fn random(rng: &mut (impl RngCore + CryptoRng)) -> Self {
+        Self(SecretKey::new(rng))
+    }
+
+    /// Read a viewing key from its 32 byte secret, or `None` if the bytes aren't a valid
+    /// secp256k1 secret key
+    pub fn from_bytes(bytes: [u8; 32]) -> Option<Self> {
+        SecretKey::from_slice(&bytes).ok().map(Self)
+    }
+
+    pub fn to_bytes(self) -> [u8; 32] {
+        self.0.secret_bytes()
+    }
+
+    /// The public key that notes are encrypted to
+    pub fn public_key(&self) -> PublicViewingKey {
+        PublicViewingKey(self.0.public_key(SECP256K1))
+    }
+}
+
+impl fmt::Display for ViewingKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&encode_bech32m(Self::HRP, self.to_bytes()))
+    }
+}
+
+/// An error produced when parsing a [`ViewingKey`] from a string
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ParseViewingKeyError {
+    /// The string was not a bech32m encoding of 32 bytes with the prefix `payyvk`
+    #[error("invalid viewing key encoding")]
+    Decode(#[from] DecodeError),
+    /// The decoded bytes were not a valid secp256k1 secret key
+    #[error("invalid viewing key")]
+    InvalidKey,
+}
+
+impl FromStr for ViewingKey {
+    type Err = ParseViewingKeyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = decode_bech32m(Self::HRP, s)?;
+        Self::from_bytes(bytes).ok_or(ParseViewingKeyError::InvalidKey)
+    }
+}
+
+impl Serialize for ViewingKey {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ViewingKey {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// The public half of a [`ViewingKey`], which senders encrypt notes to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PublicViewingKey(PublicKey);
+
+impl PublicViewingKey {
+    /// Read a public viewing key from its compressed form, or `None` if the bytes aren't a valid
+    /// secp256k1 public key
+    pub fn from_bytes(bytes: &[u8; PUBLIC_KEY_LEN]) -> Option<Self> {
+        PublicKey::from_slice(bytes).ok().map(Self)
+    }
+
+    /// The compressed form of this key
+    pub fn to_bytes(self) -> [u8; PUBLIC_KEY_LEN] {
+        self.0.serialize()
+    }
+}
+
+/// A [`Note`] encrypted to a [`PublicViewingKey`]
+#[derive(
+    Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, BorshSerialize, BorshDeserialize,
+)]
+pub struct NoteCiphertext {
+    /// The commitment of the encrypted note
+    pub commitment: Element,
+    /// The sender's ephemeral public key
+    #[serde(
+        serialize_with = "crate::util::serialize_array",
+        deserialize_with = "crate::util::deserialize_array"
+    )]
+    pub ephemeral_key: [u8; PUBLIC_KEY_LEN],
+    /// The encrypted note, including the authentication tag
+    pub ciphertext: Vec<u8>,
+}
+
+/// Encrypt `note` so it can only be read with the [`ViewingKey`] for `recipient`
+pub fn encrypt(
+    note: &Note,
+    recipient: &PublicViewingKey,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> NoteCiphertext {
+    let ephemeral_secret = SecretKey::new(rng);
+    let ephemeral_key = ephemeral_secret.public_key(SECP256K1).serialize();
+
+    let shared_secret = SharedSecret::new(&recipient.0, &ephemeral_secret);
+    let cipher = cipher(&shared_secret, &ephemeral_key);
+
+    let commitment = note.commitment();
+    let plaintext = [note.address(), note.value(), note.psi(), note.source()]
+        .map(Element::to_be_bytes)
+        .concat();
+
+    let ciphertext = cipher
+        .encrypt(
+            &Nonce::default(),
+            Payload {
+                msg: &plaintext,
+                aad: &commitment.to_be_bytes(),
+            },
+        )
+        .expect("encrypting a short message can't fail");
+
+    NoteCiphertext {
+        commitment,
+        ephemeral_key,
+        ciphertext,
+    }
+}
+
+/// Decrypt `ciphertext` with `viewing_key`
+///
+/// Returns `None` if the note wasn't encrypted to this viewing key, or if the ciphertext is
+/// invalid or doesn't match its commitment
+pub fn try_decrypt(viewing_key: &ViewingKey, ciphertext: &NoteCiphertext) -> Option<Note> {
+    let ephemeral_key = PublicKey::from_slice(&ciphertext.ephemeral_key).ok()?;
+
+    let shared_secret = SharedSecret::new(&ephemeral_key, &viewing_key.0);
+    let cipher = cipher(&shared_secret, &ciphertext.ephemeral_key);
+
+    let plaintext = cipher
+        .decrypt(
+            &Nonce::default(),
+            Payload {
+                msg: &ciphertext.ciphertext,
+                aad: &ciphertext.commitment.to_be_bytes(),
+            },
+        )
+        .ok()?;
+
+    if plaintext.len() != PLAINTEXT_LEN {
+        return None;
+    }
+
+    let [address, value, psi, source] = std::array::from_fn(|i| {
+        Element::from_be_bytes(plaintext[i * 32..(i + 1) * 32].try_into().unwrap())
+    });
+
+    let note = Note::restore(address, psi, value, source);
+
+    (note.commitment() == ciphertext.commitment).then_some(note)
+}
+
+/// Each ephemeral key is only used once, so the key is unique per note and a fixed nonce is safe
+fn cipher(shared_secret: &SharedSecret, ephemeral_key: &[u8; PUBLIC_KEY_LEN]) -> ChaCha20Poly1305 {
+    let key = Blake2bParams::new()
+        .hash_length(32)
+        .personal(KDF_PERSONALISATION)
+        .to_state()
+        .update(&shared_secret.secret_bytes())
+        .update(ephemeral_key)
+        .finalize();
+
+    ChaCha20Poly1305::new(Key::from_slice(key.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::insecure_random_element;
+    use rand::thread_rng;
+
+    fn note() -> Note {
+        Note::new(insecure_random_element(), Element::from(100u64))
+    }
+
+    #[test]
+    fn round_trip() {
+        let viewing_key = ViewingKey::random(&mut thread_rng());
+        let note = note();
+
+        let ciphertext = encrypt(&note, &viewing_key.public_key(), &mut thread_rng());
+
+        assert_eq!(ciphertext.commitment, note.commitment());
+        assert_eq!(try_decrypt(&viewing_key, &ciphertext), Some(note));
+    }
+
+    #[test]
+    fn other_viewing_key_cant_decrypt() {
+        let viewing_key = ViewingKey::random(&mut thread_rng());
+        let other_key = ViewingKey::random(&mut thread_rng());
+
+        let ciphertext = encrypt(&note(), &viewing_key.public_key(), &mut thread_rng());
+
+        assert_eq!(try_decrypt(&other_key, &ciphertext), None);
+    }
+
+    #[test]
+    fn ciphertext_is_bound_to_commitment() {
+        let viewing_key = ViewingKey::random(&mut thread_rng());
+
+        let mut ciphertext = encrypt(&note(), &viewing_key.public_key(), &mut thread_rng());
+        ciphertext.commitment = note().commitment();
+
+        assert_eq!(try_decrypt(&viewing_key, &ciphertext), None);
+    }
+
+    #[test]
+    fn viewing_key_bech32m_round_trip() {
+        let viewing_key = ViewingKey::random(&mut thread_rng());
+        let encoded = viewing_key.to_string();
+
+        assert!(encoded.starts_with("payyvk1"));
+        assert_eq!(encoded.parse(), Ok(viewing_key));
+
+        let json = serde_json::to_value(viewing_key).unwrap();
+        assert_eq!(
+            serde_json::from_value::<ViewingKey>(json).unwrap(),
+            viewing_key
+        );
+
+        // zero isn't a valid secret key
+        let zero = encode_bech32m(ViewingKey::HRP, [0; 32]);
+        assert_eq!(
+            zero.parse::<ViewingKey>(),
+            Err(ParseViewingKeyError::InvalidKey)
+        );
+
+        let address = zk_primitives::Address(Element::ONE).to_string();
+        assert!(matches!(
+            address.parse::<ViewingKey>(),
+            Err(ParseViewingKeyError::Decode(
+                DecodeError::WrongPrefix { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn tampered_ciphertext_is_rejected() {
+        let viewing_key = ViewingKey::random(&mut thread_rng());
+
+        let mut ciphertext = encrypt(&note(), &viewing_key.public_key(), &mut thread_rng());
+        ciphertext.ciphertext[0] ^= 1;
+
+        assert_eq!(try_decrypt(&viewing_key, &ciphertext), None);
+    }
+
+    #[test]
+    fn viewing_key_bytes_round_trip() {
+        let viewing_key = ViewingKey::random(&mut thread_rng());
+        let public_key = viewing_key.public_key();
+
+        assert_eq!(
+            ViewingKey::from_bytes(viewing_key.to_bytes()),
+            Some(viewing_key)
+        );
+        assert_eq!(
+            PublicViewingKey::from_bytes(&public_key.to_bytes()),
+            Some(public_key)
+        );
+    }
+}
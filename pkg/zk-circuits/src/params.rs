@@ -5,30 +5,124 @@ use halo2_base::halo2_proofs::{
     poly::{commitment::Params, kzg::commitment::ParamsKZG},
 };
 
-use crate::data::ParameterSet;
+use crate::{
+    data::ParameterSet,
+    error::{Error, ErrorKind},
+    store,
+};
 
-const BYTES_6: &[u8] = include_bytes!("../../../fixtures/params/kzg_bn254_6.srs");
-const BYTES_8: &[u8] = include_bytes!("../../../fixtures/params/kzg_bn254_8.srs");
-const BYTES_9: &[u8] = include_bytes!("../../../fixtures/params/kzg_bn254_9.srs");
-const BYTES_14: &[u8] = include_bytes!("../../../fixtures/params/kzg_bn254_14.srs");
-const BYTES_21: &[u8] = include_bytes!("../../../fixtures/params/kzg_bn254_21.srs");
+#[cfg(feature = "embedded-params")]
+mod embedded {
+    pub const BYTES_6: &[u8] = include_bytes!("../../../fixtures/params/kzg_bn254_6.srs");
+    pub const BYTES_8: &[u8] = include_bytes!("../../../fixtures/params/kzg_bn254_8.srs");
+    pub const BYTES_9: &[u8] = include_bytes!("../../../fixtures/params/kzg_bn254_9.srs");
+    pub const BYTES_14: &[u8] = include_bytes!("../../../fixtures/params/kzg_bn254_14.srs");
+    pub const BYTES_21: &[u8] = include_bytes!("../../../fixtures/params/kzg_bn254_21.srs");
+}
 
 static PARAMS_6: OnceLock<ParamsKZG<Bn256>> = OnceLock::new();
 static PARAMS_8: OnceLock<ParamsKZG<Bn256>> = OnceLock::new();
 static PARAMS_9: OnceLock<ParamsKZG<Bn256>> = OnceLock::new();
 static PARAMS_14: OnceLock<ParamsKZG<Bn256>> = OnceLock::new();
+static PARAMS_15: OnceLock<ParamsKZG<Bn256>> = OnceLock::new();
+static PARAMS_16: OnceLock<ParamsKZG<Bn256>> = OnceLock::new();
 static PARAMS_21: OnceLock<ParamsKZG<Bn256>> = OnceLock::new();
 
-fn load(bytes: &[u8]) -> ParamsKZG<Bn256> {
-    ParamsKZG::read(&mut Cursor::new(bytes)).unwrap()
+impl ParameterSet {
+    /// The log2 of the number of rows these params support
+    pub fn k(&self) -> u32 {
+        match self {
+            Self::Six => 6,
+            Self::Eight => 8,
+            Self::Nine => 9,
+            Self::Fourteen => 14,
+            Self::Fifteen => 15,
+            Self::Sixteen => 16,
+            Self::TwentyOne => 21,
+        }
+    }
+
+    /// The name of the SRS file for these params
+    pub fn file_name(&self) -> String {
+        format!("kzg_bn254_{}.srs", self.k())
+    }
+
+    /// The sha256 of the SRS file, or `None` for params that are downsized from larger params
+    pub(crate) fn checksum(&self) -> Option<&'static str> {
+        match self {
+            Self::Six => Some("e987af522575abf235040a58a92e75a275a3696674c6ab0c42deef723d1e0558"),
+            Self::Eight => Some("9144792cc0696f192987881b4396354639c48a97138325510f46acf80eb676c7"),
+            Self::Nine => Some("26153128b08b72c147a5f4cd04d5feaea9eba9d1e662b860c3864cd0686a70b9"),
+            Self::Fourteen => {
+                Some("a6f19f9d28c6fbad4ed6cea3ff9ff5e518929d785571017061b5dc0105454b94")
+            }
+            Self::TwentyOne => {
+                Some("8e999d1361d5cd8b756ff5980e2e19b3a8f717a5347fd1669397a0dec6eb3df4")
+            }
+            Self::Fifteen | Self::Sixteen => None,
+        }
+    }
+
+    /// The SRS file compiled into the binary, if the `embedded-params` feature is enabled
+    pub(crate) fn embedded(&self) -> Option<&'static [u8]> {
+        #[cfg(feature = "embedded-params")]
+        {
+            match self {
+                Self::Six => Some(embedded::BYTES_6),
+                Self::Eight => Some(embedded::BYTES_8),
+                Self::Nine => Some(embedded::BYTES_9),
+                Self::Fourteen => Some(embedded::BYTES_14),
+                Self::TwentyOne => Some(embedded::BYTES_21),
+                Self::Fifteen | Self::Sixteen => None,
+            }
+        }
+
+        #[cfg(not(feature = "embedded-params"))]
+        None
+    }
 }
 
-pub(crate) fn load_params(params: ParameterSet) -> &'static ParamsKZG<Bn256> {
-    match params {
-        ParameterSet::Six => PARAMS_6.get_or_init(|| load(BYTES_6)),
-        ParameterSet::Eight => PARAMS_8.get_or_init(|| load(BYTES_8)),
-        ParameterSet::Nine => PARAMS_9.get_or_init(|| load(BYTES_9)),
-        ParameterSet::Fourteen => PARAMS_14.get_or_init(|| load(BYTES_14)),
-        ParameterSet::TwentyOne => PARAMS_21.get_or_init(|| load(BYTES_21)),
+/// Load params from the [store][crate::store], falling back to the embedded params
+fn load(params: ParameterSet) -> crate::Result<ParamsKZG<Bn256>> {
+    let bytes = store::global().read_params(params)?;
+
+    ParamsKZG::read(&mut Cursor::new(bytes)).map_err(|err| {
+        Error::new(
+            ErrorKind::Other,
+            format!("invalid {params:?} params: {err}"),
+        )
+    })
+}
+
+/// Params for sizes without their own fixture are taken from a prefix of the largest params, which
+/// come from the same setup
+fn downsize(params: ParameterSet) -> crate::Result<ParamsKZG<Bn256>> {
+    let mut downsized = load_params(ParameterSet::TwentyOne)?.clone();
+    downsized.downsize(params.k());
+    Ok(downsized)
+}
+
+/// The params for `params`, which are loaded once and then cached
+///
+/// Returns an error if the params aren't in the [store][crate::store] or embedded, or don't match
+/// their checksum. A failed load isn't cached, so it is retried on the next call
+pub(crate) fn load_params(params: ParameterSet) -> crate::Result<&'static ParamsKZG<Bn256>> {
+    type Init = fn(ParameterSet) -> crate::Result<ParamsKZG<Bn256>>;
+
+    let (cache, init): (&OnceLock<_>, Init) = match params {
+        ParameterSet::Six => (&PARAMS_6, load),
+        ParameterSet::Eight => (&PARAMS_8, load),
+        ParameterSet::Nine => (&PARAMS_9, load),
+        ParameterSet::Fourteen => (&PARAMS_14, load),
+        ParameterSet::Fifteen => (&PARAMS_15, downsize),
+        ParameterSet::Sixteen => (&PARAMS_16, downsize),
+        ParameterSet::TwentyOne => (&PARAMS_21, load),
+    };
+
+    if let Some(loaded) = cache.get() {
+        return Ok(loaded);
     }
+
+    let loaded = init(params)?;
+    Ok(cache.get_or_init(|| loaded))
 }
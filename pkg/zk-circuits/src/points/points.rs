@@ -2,6 +2,7 @@ use crate::chips::add::AddCulmChip;
 use crate::chips::is_constant::IsConstantChip;
 use crate::chips::swap::CondSwapChip;
 use crate::data::{Note, ParameterSet, Points};
+use crate::error::ErrorKind;
 use crate::params::load_params;
 use crate::util::keygen_from_params;
 use crate::Snark;
@@ -20,9 +21,16 @@ use zk_primitives::Element;
 const NUM_NOTES: usize = 112;
 
 impl Points {
-    pub fn new(secret_key: Element, notes: Vec<Note>) -> Self {
-        assert!(notes.len() == NUM_NOTES, "notes must be 112");
-        Self { secret_key, notes }
+    /// Returns an [`ErrorKind::InvalidInput`] error if there aren't exactly 112 notes
+    pub fn new(secret_key: Element, notes: Vec<Note>) -> crate::Result<Self> {
+        if notes.len() != NUM_NOTES {
+            return Err(crate::Error::new(
+                ErrorKind::InvalidInput,
+                format!("expected {NUM_NOTES} notes, got {}", notes.len()),
+            ));
+        }
+
+        Ok(Self { secret_key, notes })
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -96,14 +104,11 @@ impl Points {
         inputs
     }
 
-    pub fn snark(&self, params: ParameterSet) -> Result<Snark, Error> {
+    pub fn snark(&self, params: ParameterSet) -> crate::Result<Snark> {
+        let kzg_params = load_params(params)?;
         let (pk, _) = self.keygen(params);
-        Snark::create(
-            self.clone(),
-            vec![self.public_inputs()],
-            load_params(params),
-            &pk,
-        )
+
+        Snark::create(self.clone(), vec![self.public_inputs()], kzg_params, &pk)
     }
 
     pub fn keygen(&self, params: ParameterSet) -> (ProvingKey<G1Affine>, VerifyingKey<G1Affine>) {
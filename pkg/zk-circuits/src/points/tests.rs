@@ -22,7 +22,7 @@ fn test_one_note() {
         notes.push(Note::padding_note())
     }
 
-    let circuit = Points::new(pk, notes);
+    let circuit = Points::new(pk, notes).unwrap();
     let public_input = circuit.public_inputs();
 
     assert_eq!(public_input.len(), 2 + (112 * 2));
@@ -35,3 +35,11 @@ fn test_one_note() {
     let prover = MockProver::<Fr>::run(k, &circuit, instance_columns).unwrap();
     prover.assert_satisfied();
 }
+
+#[test]
+fn test_wrong_number_of_notes() {
+    let notes = vec![Note::padding_note(); 3];
+
+    let err = Points::new(Element::ONE, notes).unwrap_err();
+    assert_eq!(err.kind(), crate::ErrorKind::InvalidInput);
+}
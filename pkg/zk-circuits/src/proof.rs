@@ -18,6 +18,8 @@ use halo2_base::halo2_proofs::{
     transcript::{Blake2bRead, Blake2bWrite, TranscriptReadBuffer, TranscriptWriterBuffer},
 };
 use rand::RngCore;
+
+use crate::error::catch_panic;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -32,17 +34,20 @@ impl Proof {
         circuit: C,
         instances: &[&[Fr]],
         rng: impl RngCore,
-    ) -> Result<Self, plonk::Error> {
-        let mut transcript = Blake2bWrite::<_, <Bn256 as Engine>::G1Affine, _>::init(Vec::new());
-        plonk::create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<Bn256>, _, _, _, _>(
-            params,
-            pk,
-            &[circuit],
-            &[instances],
-            rng,
-            &mut transcript,
-        )?;
-        Ok(Self(transcript.finalize()))
+    ) -> crate::Result<Self> {
+        catch_panic(|| {
+            let mut transcript =
+                Blake2bWrite::<_, <Bn256 as Engine>::G1Affine, _>::init(Vec::new());
+            plonk::create_proof::<KZGCommitmentScheme<Bn256>, ProverSHPLONK<Bn256>, _, _, _, _>(
+                params,
+                pk,
+                &[circuit],
+                &[instances],
+                rng,
+                &mut transcript,
+            )?;
+            Ok(Self(transcript.finalize()))
+        })
     }
 
     // TODO: this should be generic, as `create` above
@@ -53,17 +58,20 @@ impl Proof {
         vk: &VerifyingKey<G1Affine>,
         params: &ParamsKZG<Bn256>,
         instances: &[&[Fr]],
-    ) -> Result<(), plonk::Error> {
-        let strategy = SingleStrategy::new(params);
-        let mut transcript =
-            Blake2bRead::<_, <Bn256 as Engine>::G1Affine, _>::init(Cursor::new(self.0.clone()));
-        plonk::verify_proof::<_, VerifierSHPLONK<_>, _, _, _>(
-            params.verifier_params(),
-            vk,
-            strategy,
-            &[instances],
-            &mut transcript,
-        )
+    ) -> crate::Result<()> {
+        catch_panic(|| {
+            let strategy = SingleStrategy::new(params);
+            let mut transcript =
+                Blake2bRead::<_, <Bn256 as Engine>::G1Affine, _>::init(Cursor::new(self.0.clone()));
+            plonk::verify_proof::<_, VerifierSHPLONK<_>, _, _, _>(
+                params.verifier_params(),
+                vk,
+                strategy,
+                &[instances],
+                &mut transcript,
+            )?;
+            Ok(())
+        })
     }
 
     /// Constructs a new Proof value.
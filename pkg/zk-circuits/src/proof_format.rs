@@ -1,9 +1,9 @@
 use halo2_base::halo2_proofs::{
-    halo2curves::bn256::{Fr, G1Affine},
-    plonk::{verify_proof, VerifyingKey},
+    halo2curves::bn256::{Bn256, Fr, G1Affine},
+    plonk::{self, verify_proof, VerifyingKey},
     poly::{
         commitment::ParamsProver,
-        kzg::{multiopen::VerifierSHPLONK, strategy::AccumulatorStrategy},
+        kzg::{commitment::ParamsKZG, multiopen::VerifierSHPLONK, strategy::AccumulatorStrategy},
         VerificationStrategy,
     },
     transcript::TranscriptWriterBuffer,
@@ -14,8 +14,9 @@ use smirk::Element;
 use snark_verifier::loader::native::NativeLoader;
 
 use crate::{
-    chips::aggregation::types::PoseidonTranscript,
+    chips::aggregation::{snark::check_instance_columns, types::PoseidonTranscript},
     data::{ParameterSet, SnarkWitnessV1},
+    error::{catch_panic, Error, ErrorKind},
     keys::CircuitKind,
     params::load_params,
     Snark,
@@ -26,7 +27,11 @@ impl SnarkWitnessV1 {
         Self { instances, proof }
     }
 
-    pub fn to_snark(&self, vk: &VerifyingKey<G1Affine>, params: ParameterSet) -> Snark {
+    pub fn to_snark(
+        &self,
+        vk: &VerifyingKey<G1Affine>,
+        params: ParameterSet,
+    ) -> crate::Result<Snark> {
         Snark::from_witness(self.clone(), vk, params)
     }
 
@@ -37,26 +42,135 @@ impl SnarkWitnessV1 {
             .collect()
     }
 
-    pub fn verify(&self, kind: CircuitKind) -> bool {
-        let params = load_params(kind.params());
-        let vk = kind.vk();
+    /// Verify this proof with the keys for `kind`
+    ///
+    /// Returns an [`ErrorKind::InvalidProof`] error if the proof is malformed or doesn't verify
+    pub fn verify(&self, kind: CircuitKind) -> crate::Result<()> {
+        let vk = kind.vk()?;
+        check_instance_columns(self, vk)?;
 
-        let mut transcript =
-            PoseidonTranscript::<NativeLoader, _>::init(Cursor::new(self.proof.clone()));
+        catch_panic(|| {
+            let params = load_params(kind.params())?;
 
-        VerificationStrategy::<_, VerifierSHPLONK<_>>::finalize(
-            verify_proof::<_, VerifierSHPLONK<_>, _, PoseidonTranscript<NativeLoader, _>, _>(
-                params.verifier_params(),
+            let accumulator = self.accumulate(
+                params,
                 vk,
                 AccumulatorStrategy::new(params.verifier_params()),
-                &[&self
-                    .fr_instances()
-                    .iter()
-                    .map(|v| v.as_slice())
-                    .collect::<Vec<_>>()],
-                &mut transcript,
-            )
-            .unwrap(),
+            )?;
+
+            if !VerificationStrategy::<_, VerifierSHPLONK<_>>::finalize(accumulator) {
+                return Err(Error::new(
+                    ErrorKind::InvalidProof,
+                    "final pairing check failed",
+                ));
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Verify many proofs with the keys for `kind`, returning the indices of the invalid proofs
+    ///
+    /// The proofs are accumulated and checked with a single pairing, rather than one pairing per
+    /// proof as with [`SnarkWitnessV1::verify`]. If the batch fails, it is split in half and each
+    /// half is checked again, until the invalid proofs are found. So a batch with a few invalid
+    /// proofs costs `O(invalid * log(len))` pairings.
+    ///
+    /// An empty list means every proof is valid. `Err` is only returned for errors that aren't
+    /// caused by the proofs (e.g. the keys for `kind` failing to load).
+    pub fn verify_batch(kind: CircuitKind, witnesses: &[Self]) -> crate::Result<Vec<usize>> {
+        let vk = kind.vk()?;
+        let params = load_params(kind.params())?;
+
+        let (candidates, mut invalid): (Vec<usize>, Vec<usize>) = (0..witnesses.len())
+            .partition(|&index| check_instance_columns(&witnesses[index], vk).is_ok());
+
+        let is_valid = |indices: &[usize]| {
+            catch_panic(|| {
+                let mut accumulator = AccumulatorStrategy::new(params.verifier_params());
+
+                for &index in indices {
+                    accumulator = witnesses[index].accumulate(params, vk, accumulator)?;
+                }
+
+                Ok(VerificationStrategy::<_, VerifierSHPLONK<_>>::finalize(
+                    accumulator,
+                ))
+            })
+            .unwrap_or(false)
+        };
+
+        bisect(&candidates, &is_valid, &mut invalid);
+
+        invalid.sort_unstable();
+        Ok(invalid)
+    }
+
+    /// Add this proof to `accumulator`, without doing the final pairing check
+    ///
+    /// The strategy scales the existing accumulator by a random challenge before adding the proof,
+    /// so one invalid proof can't cancel out another
+    fn accumulate<'params>(
+        &self,
+        params: &'params ParamsKZG<Bn256>,
+        vk: &VerifyingKey<G1Affine>,
+        accumulator: AccumulatorStrategy<'params, Bn256>,
+    ) -> Result<AccumulatorStrategy<'params, Bn256>, plonk::Error> {
+        let mut transcript =
+            PoseidonTranscript::<NativeLoader, _>::init(Cursor::new(self.proof.clone()));
+
+        verify_proof::<_, VerifierSHPLONK<_>, _, PoseidonTranscript<NativeLoader, _>, _>(
+            params.verifier_params(),
+            vk,
+            accumulator,
+            &[&self
+                .fr_instances()
+                .iter()
+                .map(|v| v.as_slice())
+                .collect::<Vec<_>>()],
+            &mut transcript,
         )
     }
 }
+
+/// Push the indices of the invalid proofs in `indices` to `invalid`, checking halves of the batch
+/// recursively
+fn bisect(indices: &[usize], is_valid: &impl Fn(&[usize]) -> bool, invalid: &mut Vec<usize>) {
+    if indices.is_empty() || is_valid(indices) {
+        return;
+    }
+
+    if let [index] = indices {
+        invalid.push(*index);
+        return;
+    }
+
+    let (left, right) = indices.split_at(indices.len() / 2);
+    bisect(left, is_valid, invalid);
+    bisect(right, is_valid, invalid);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bisect_finds_every_invalid_index() {
+        let bad = [3, 4, 17];
+        let checks = std::cell::Cell::new(0);
+        let is_valid = |indices: &[usize]| {
+            checks.set(checks.get() + 1);
+            !indices.iter().any(|index| bad.contains(index))
+        };
+
+        let mut invalid = vec![];
+        bisect(&(0..32).collect::<Vec<_>>(), &is_valid, &mut invalid);
+        assert_eq!(invalid, bad);
+        assert!(checks.get() < 32);
+
+        let mut invalid = vec![];
+        bisect(&[], &is_valid, &mut invalid);
+        bisect(&[0, 1, 2], &is_valid, &mut invalid);
+        assert!(invalid.is_empty());
+    }
+}
@@ -0,0 +1,325 @@
+//! One API for proving and verifying every circuit, whatever its transcript
+//!
+//! The crate has a proving path per transcript:
+//!  - [`Snark::create`] uses Poseidon, which is cheap to verify in the aggregation circuits
+//!  - [`evm_verifier::gen_proof`] uses Keccak, which the EVM verifiers expect
+//!  - [`Proof::create`] uses Blake2b
+//!
+//! A [`ProofSystem`] picks the transcript once, and produces a [`CircuitProof`] that records the
+//! transcript, the circuit kind and the hash of the verifying key it was made for. So
+//! [`CircuitProof::verify`] takes the right path without the caller knowing which one each
+//! circuit needs.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use halo2_base::halo2_proofs::{
+    halo2curves::bn256::{Fr, G1Affine},
+    plonk::{Circuit, VerifyingKey},
+    SerdeFormat,
+};
+use rand::rngs::OsRng;
+use serde::{Deserialize, Serialize};
+use zk_primitives::Element;
+
+use crate::{
+    chips::aggregation::snark::check_instance_columns,
+    data::SnarkWitnessV1,
+    error::{catch_panic, Error, ErrorKind},
+    evm_verifier,
+    params::load_params,
+    proof::Proof,
+    store, CircuitKind, Snark,
+};
+
+/// The transcript that a proof's challenges are derived with
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum TranscriptKind {
+    /// Poseidon, so the proof can be verified in the aggregation circuits
+    Native,
+    /// Keccak, so the proof can be verified by the EVM verifiers
+    Evm,
+    /// Blake2b, for proofs that are only verified off-chain with [`Proof::verify`]
+    Blake2b,
+}
+
+impl TranscriptKind {
+    /// The transcript that proofs of `kind` are verified with: [`TranscriptKind::Evm`] for the
+    /// circuits with an EVM verifier, and [`TranscriptKind::Native`] for those that are aggregated
+    pub fn for_circuit(kind: CircuitKind) -> Self {
+        match kind {
+            CircuitKind::Burn | CircuitKind::Mint => Self::Evm,
+            _ => Self::Native,
+        }
+    }
+}
+
+/// Creates [`CircuitProof`]s with a [`TranscriptKind`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProofSystem {
+    transcript: TranscriptKind,
+}
+
+impl ProofSystem {
+    pub fn new(transcript: TranscriptKind) -> Self {
+        Self { transcript }
+    }
+
+    /// The proof system with the transcript that proofs of `kind` are verified with, see
+    /// [`TranscriptKind::for_circuit`]
+    pub fn for_circuit(kind: CircuitKind) -> Self {
+        Self::new(TranscriptKind::for_circuit(kind))
+    }
+
+    pub fn transcript(&self) -> TranscriptKind {
+        self.transcript
+    }
+
+    /// Prove `circuit` with the keys for `kind`
+    ///
+    /// Returns an [`ErrorKind::KeyMismatch`] error if the proving key for `kind` doesn't match the
+    /// verifying key that [`CircuitProof::verify`] checks the proof against, and an
+    /// [`ErrorKind::Panic`] error if the prover panics (e.g. `circuit` doesn't fit in the params)
+    pub fn prove<C: Circuit<Fr>>(
+        &self,
+        kind: CircuitKind,
+        circuit: C,
+        instances: Vec<Vec<Fr>>,
+    ) -> crate::Result<CircuitProof> {
+        let pk = kind.pk()?;
+        let expected = vk_hash(kind.vk()?);
+        if vk_hash(pk.get_vk()) != expected {
+            return Err(Error::new(
+                ErrorKind::KeyMismatch,
+                format!(
+                    "proving key for {} doesn't match its verifying key",
+                    kind.name()
+                ),
+            ));
+        }
+
+        let slices = instances.iter().map(Vec::as_slice).collect::<Vec<_>>();
+
+        let proof = catch_panic(|| {
+            Ok(match self.transcript {
+                TranscriptKind::Native => {
+                    let params = load_params(kind.params())?;
+                    Snark::create(circuit, instances.clone(), params, pk)?.proof
+                }
+                TranscriptKind::Evm => {
+                    evm_verifier::gen_proof(kind.params(), pk, circuit, &slices)?
+                }
+                TranscriptKind::Blake2b => {
+                    let params = load_params(kind.params())?;
+                    Proof::create(params, pk, circuit, &slices, OsRng)?.inner()
+                }
+            })
+        })?;
+
+        Ok(CircuitProof::V1(CircuitProofV1 {
+            transcript: self.transcript,
+            circuit: kind.name().to_owned(),
+            vk_hash: expected,
+            instances: instances
+                .iter()
+                .map(|column| column.iter().copied().map(Element::from).collect())
+                .collect(),
+            proof,
+        }))
+    }
+}
+
+/// A proof that records how it was made, so it can be verified without knowing its transcript
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[wire_message::wire_message(type_id = "zk_circuits::CircuitProof")]
+pub enum CircuitProof {
+    V1(CircuitProofV1),
+}
+
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+pub struct CircuitProofV1 {
+    pub transcript: TranscriptKind,
+    /// The [`CircuitKind::name`] of the circuit
+    pub circuit: String,
+    /// The sha256 of the verifying key that the proof was made for
+    pub vk_hash: String,
+    pub instances: Vec<Vec<Element>>,
+    #[serde(
+        serialize_with = "crate::util::serialize_base64",
+        deserialize_with = "crate::util::deserialize_base64"
+    )]
+    pub proof: Vec<u8>,
+}
+
+impl CircuitProof {
+    pub fn transcript(&self) -> TranscriptKind {
+        match self {
+            Self::V1(proof) => proof.transcript,
+        }
+    }
+
+    /// The kind of circuit that this is a proof of
+    ///
+    /// Returns an [`ErrorKind::InvalidProof`] error if the circuit is unknown
+    pub fn kind(&self) -> crate::Result<CircuitKind> {
+        let Self::V1(proof) = self;
+
+        CircuitKind::from_name(&proof.circuit).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidProof,
+                format!("unknown circuit {:?}", proof.circuit),
+            )
+        })
+    }
+
+    /// Verify this proof with the keys for its circuit, using the transcript it was made with
+    ///
+    /// Returns an [`ErrorKind::KeyMismatch`] error if the proof was made for a different verifying
+    /// key than the one this crate has for the circuit
+    pub fn verify(&self) -> crate::Result<()> {
+        let Self::V1(proof) = self;
+        let kind = self.kind()?;
+        let vk = kind.vk()?;
+
+        let expected = vk_hash(vk);
+        if proof.vk_hash != expected {
+            return Err(Error::new(
+                ErrorKind::KeyMismatch,
+                format!(
+                    "proof is for verifying key {}, expected {expected}",
+                    proof.vk_hash
+                ),
+            ));
+        }
+
+        let witness = self.to_snark_witness();
+
+        match proof.transcript {
+            TranscriptKind::Native => witness.verify(kind),
+            TranscriptKind::Evm => {
+                evm_verifier::verify_proof(kind, &proof.proof, &witness.fr_instances())
+            }
+            TranscriptKind::Blake2b => {
+                check_instance_columns(&witness, vk)?;
+
+                let instances = witness.fr_instances();
+                let slices = instances.iter().map(Vec::as_slice).collect::<Vec<_>>();
+
+                Proof::new(proof.proof.clone()).verify(vk, load_params(kind.params())?, &slices)
+            }
+        }
+    }
+
+    /// The instances and proof, without how the proof was made
+    pub fn to_snark_witness(&self) -> SnarkWitnessV1 {
+        let Self::V1(proof) = self;
+        SnarkWitnessV1::new(proof.instances.clone(), proof.proof.clone())
+    }
+}
+
+fn vk_hash(vk: &VerifyingKey<G1Affine>) -> String {
+    store::sha256_hex(&vk.to_bytes(SerdeFormat::Processed))
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::thread_rng;
+    use wire_message::WireMessage;
+
+    use super::*;
+    use crate::data::{Signature, Utxo};
+
+    fn prove(transcript: TranscriptKind) -> CircuitProof {
+        let circuit = Signature::new(
+            Element::secure_random(thread_rng()),
+            Element::secure_random(thread_rng()),
+        );
+        let instances = vec![circuit.public_inputs()];
+
+        ProofSystem::new(transcript)
+            .prove(CircuitKind::Signature, circuit, instances)
+            .unwrap()
+    }
+
+    #[test]
+    fn every_transcript_verifies() {
+        for transcript in [
+            TranscriptKind::Native,
+            TranscriptKind::Evm,
+            TranscriptKind::Blake2b,
+        ] {
+            let proof = prove(transcript);
+
+            assert_eq!(proof.transcript(), transcript);
+            assert_eq!(proof.kind().unwrap(), CircuitKind::Signature);
+            proof.verify().unwrap();
+
+            let bytes = proof.to_bytes().unwrap();
+            let decoded = CircuitProof::from_bytes(&bytes).unwrap();
+            assert_eq!(decoded, proof);
+            decoded.verify().unwrap();
+        }
+    }
+
+    #[test]
+    fn utxo_proof_verifies() {
+        let circuit = Utxo::<161>::default();
+        let instances = vec![circuit.public_inputs()];
+
+        let proof = ProofSystem::for_circuit(CircuitKind::Utxo)
+            .prove(CircuitKind::Utxo, circuit, instances)
+            .unwrap();
+
+        assert_eq!(proof.transcript(), TranscriptKind::Native);
+        assert_eq!(proof.kind().unwrap(), CircuitKind::Utxo);
+        proof.verify().unwrap();
+    }
+
+    #[test]
+    fn wrong_metadata_is_rejected() {
+        let CircuitProof::V1(proof) = prove(TranscriptKind::Native);
+
+        let wrong_transcript = CircuitProof::V1(CircuitProofV1 {
+            transcript: TranscriptKind::Blake2b,
+            ..proof.clone()
+        });
+        let err = wrong_transcript.verify().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidProof);
+
+        let wrong_key = CircuitProof::V1(CircuitProofV1 {
+            vk_hash: "00".repeat(32),
+            ..proof.clone()
+        });
+        let err = wrong_key.verify().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::KeyMismatch);
+
+        let unknown_circuit = CircuitProof::V1(CircuitProofV1 {
+            circuit: "unknown".to_owned(),
+            ..proof
+        });
+        let err = unknown_circuit.verify().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidProof);
+    }
+
+    #[test]
+    fn transcript_for_circuit() {
+        assert_eq!(
+            ProofSystem::for_circuit(CircuitKind::Burn).transcript(),
+            TranscriptKind::Evm
+        );
+        assert_eq!(
+            ProofSystem::for_circuit(CircuitKind::Utxo).transcript(),
+            TranscriptKind::Native
+        );
+    }
+}
@@ -0,0 +1,376 @@
+//! Where KZG params and proving keys are loaded from
+//!
+//! By default, params are compiled into the binary (with the `embedded-params` feature) and
+//! proving keys are generated on first use. A [`Store`] is a list of directories to look in first,
+//! so binaries can be built without the params, and can skip keygen on startup.
+//!
+//! A store directory contains:
+//!  - SRS files named by [`ParameterSet::file_name`] (e.g. `kzg_bn254_14.srs`), which must match
+//!    the pinned checksum of the setup
+//!  - proving keys named by [`CircuitKind::name`] (e.g. `utxo.pk`), which must be listed with
+//!    their sha256 in the directory's `manifest.json` (see [`write_keys`])
+//!
+//! A proving key whose verifying key doesn't match the [pinned][CircuitKind::pinned_vk_hash]
+//! hash is rejected when it is loaded.
+//!
+//! The directories are searched in order, and the first file found is used. A file that doesn't
+//! match its checksum is an error, rather than falling through to the next directory.
+//!
+//! The global store is read from the `ZK_CIRCUITS_STORE` environment variable (a list of paths,
+//! in the same format as `PATH`), unless [`init`] is called before any params or keys are loaded.
+
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use halo2_base::halo2_proofs::SerdeFormat;
+use sha2::{Digest, Sha256};
+
+use crate::{data::ParameterSet, CircuitKind};
+
+/// The environment variable that the global store's paths are read from
+pub const STORE_ENV_VAR: &str = "ZK_CIRCUITS_STORE";
+
+/// The name of the file in a store directory with the checksums of its keys
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+static STORE: OnceLock<Store> = OnceLock::new();
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("io error for {}: {source}", path.display())]
+    Io { path: PathBuf, source: io::Error },
+    #[error("invalid manifest {}: {source}", path.display())]
+    Manifest {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    #[error("checksum of {} is {actual}, expected {expected}", path.display())]
+    Checksum {
+        path: PathBuf,
+        expected: String,
+        actual: String,
+    },
+    #[error("{} is not in the manifest", path.display())]
+    NotInManifest { path: PathBuf },
+    #[error("{0} was not found in the store, and is not embedded")]
+    NotFound(String),
+}
+
+/// The sha256 of each proving key in a store directory, by file name
+pub type Manifest = BTreeMap<String, String>;
+
+/// The directories that params and proving keys are loaded from
+#[derive(Debug, Clone)]
+pub struct Store {
+    paths: Vec<PathBuf>,
+    embedded: bool,
+}
+
+impl Default for Store {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Store {
+    /// A store with no directories, which only uses the embedded params
+    pub fn new() -> Self {
+        Self {
+            paths: vec![],
+            embedded: true,
+        }
+    }
+
+    /// A store with the directories in [`STORE_ENV_VAR`]
+    pub fn from_env() -> Self {
+        let paths = std::env::var_os(STORE_ENV_VAR)
+            .map(|paths| std::env::split_paths(&paths).collect())
+            .unwrap_or_default();
+
+        Self {
+            paths,
+            embedded: true,
+        }
+    }
+
+    /// Search `path` after the existing directories
+    pub fn with_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.paths.push(path.into());
+        self
+    }
+
+    /// Don't fall back to the embedded params, so missing params are an error
+    pub fn without_embedded(mut self) -> Self {
+        self.embedded = false;
+        self
+    }
+
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.paths
+    }
+
+    /// Read the SRS file for `params`, falling back to the embedded params
+    pub fn read_params(&self, params: ParameterSet) -> Result<Cow<'static, [u8]>, Error> {
+        let file_name = params.file_name();
+
+        if let Some(path) = self.find(&file_name) {
+            let bytes = read(&path)?;
+
+            if let Some(expected) = params.checksum() {
+                check(&path, &bytes, expected)?;
+            }
+
+            return Ok(Cow::Owned(bytes));
+        }
+
+        params
+            .embedded()
+            .filter(|_| self.embedded)
+            .map(Cow::Borrowed)
+            .ok_or(Error::NotFound(file_name))
+    }
+
+    /// Read the serialized proving key for `kind`, or `None` if no directory has one
+    pub fn read_proving_key(&self, kind: CircuitKind) -> Result<Option<Vec<u8>>, Error> {
+        let file_name = proving_key_file_name(kind);
+
+        let Some(path) = self.find(&file_name) else {
+            return Ok(None);
+        };
+
+        let manifest_path = path.with_file_name(MANIFEST_FILE_NAME);
+        let manifest = read_manifest(&manifest_path)?;
+        let expected = manifest
+            .get(&file_name)
+            .ok_or_else(|| Error::NotInManifest { path: path.clone() })?;
+
+        let bytes = read(&path)?;
+        check(&path, &bytes, expected)?;
+
+        Ok(Some(bytes))
+    }
+
+    fn find(&self, file_name: &str) -> Option<PathBuf> {
+        self.paths
+            .iter()
+            .map(|dir| dir.join(file_name))
+            .find(|path| path.is_file())
+    }
+}
+
+/// Set the global store
+///
+/// This must be called before any params or keys are loaded, otherwise the store is read from
+/// [`STORE_ENV_VAR`] and `Err` is returned with the unused store
+pub fn init(store: Store) -> Result<(), Store> {
+    STORE.set(store)
+}
+
+pub(crate) fn global() -> &'static Store {
+    STORE.get_or_init(Store::from_env)
+}
+
+/// Generate the keys for `kinds`, write them to `dir`, and add their checksums to the manifest
+///
+/// Proving keys are written as `<name>.pk` and verifying keys as `<name>.vk`. The keys are always
+/// generated from the circuits, not read from a store. Generating the keys can take a long time,
+/// so this is intended to be run once at build time (see the `keygen` binary).
+pub fn write_keys(dir: &Path, kinds: &[CircuitKind]) -> Result<Manifest, Error> {
+    let io_err = |path: &Path| {
+        let path = path.to_owned();
+        move |source| Error::Io { path, source }
+    };
+
+    fs::create_dir_all(dir).map_err(io_err(dir))?;
+
+    let manifest_path = dir.join(MANIFEST_FILE_NAME);
+    let mut manifest = if manifest_path.is_file() {
+        read_manifest(&manifest_path)?
+    } else {
+        Manifest::new()
+    };
+
+    for kind in kinds {
+        let (pk, vk) = kind.keygen();
+
+        for (file_name, bytes) in [
+            (
+                proving_key_file_name(*kind),
+                pk.to_bytes(SerdeFormat::RawBytes),
+            ),
+            (
+                verifying_key_file_name(*kind),
+                vk.to_bytes(SerdeFormat::Processed),
+            ),
+        ] {
+            let path = dir.join(&file_name);
+            fs::write(&path, &bytes).map_err(io_err(&path))?;
+
+            manifest.insert(file_name, sha256_hex(&bytes));
+        }
+    }
+
+    fs::write(&manifest_path, to_json(&manifest)).map_err(io_err(&manifest_path))?;
+
+    Ok(manifest)
+}
+
+/// A manifest with the checksums of the verifying keys for `kinds`, generated from the circuits
+pub fn verifying_key_manifest(kinds: &[CircuitKind]) -> String {
+    let manifest = kinds
+        .iter()
+        .map(|kind| {
+            let (_, vk) = kind.keygen();
+            let bytes = vk.to_bytes(SerdeFormat::Processed);
+
+            (verifying_key_file_name(*kind), sha256_hex(&bytes))
+        })
+        .collect::<Manifest>();
+
+    to_json(&manifest)
+}
+
+pub fn proving_key_file_name(kind: CircuitKind) -> String {
+    format!("{}.pk", kind.name())
+}
+
+pub fn verifying_key_file_name(kind: CircuitKind) -> String {
+    format!("{}.vk", kind.name())
+}
+
+pub(crate) fn sha256_hex(bytes: &[u8]) -> String {
+    hex::encode(Sha256::digest(bytes))
+}
+
+pub(crate) fn to_json(manifest: &Manifest) -> String {
+    let json = serde_json::to_string_pretty(manifest).expect("manifest is serializable");
+    json + "\n"
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, Error> {
+    fs::read(path).map_err(|source| Error::Io {
+        path: path.to_owned(),
+        source,
+    })
+}
+
+fn read_manifest(path: &Path) -> Result<Manifest, Error> {
+    let bytes = read(path)?;
+    serde_json::from_slice(&bytes).map_err(|source| Error::Manifest {
+        path: path.to_owned(),
+        source,
+    })
+}
+
+fn check(path: &Path, bytes: &[u8], expected: &str) -> Result<(), Error> {
+    let actual = sha256_hex(bytes);
+
+    if actual != expected {
+        return Err(Error::Checksum {
+            path: path.to_owned(),
+            expected: expected.to_owned(),
+            actual,
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(all(test, feature = "embedded-params"))]
+mod tests {
+    use super::*;
+
+    /// A fresh directory for a test
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("zk-circuits-store-{name}"));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn missing_params_fall_back_to_embedded() {
+        let store = Store::new().with_path(temp_dir("embedded"));
+
+        let bytes = store.read_params(ParameterSet::Six).unwrap();
+        assert!(matches!(bytes, Cow::Borrowed(_)));
+        assert_eq!(sha256_hex(&bytes), ParameterSet::Six.checksum().unwrap());
+
+        assert!(matches!(
+            store.without_embedded().read_params(ParameterSet::Six),
+            Err(Error::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn params_are_read_from_disk() {
+        let dir = temp_dir("params");
+        let embedded = ParameterSet::Six.embedded().unwrap();
+        fs::write(dir.join(ParameterSet::Six.file_name()), embedded).unwrap();
+
+        let store = Store::new().with_path(&dir).without_embedded();
+        let bytes = store.read_params(ParameterSet::Six).unwrap();
+
+        assert!(matches!(bytes, Cow::Owned(_)));
+        assert_eq!(&*bytes, embedded);
+    }
+
+    #[test]
+    fn corrupt_params_are_rejected() {
+        let dir = temp_dir("corrupt");
+        let mut bytes = ParameterSet::Six.embedded().unwrap().to_vec();
+        bytes[100] ^= 1;
+        fs::write(dir.join(ParameterSet::Six.file_name()), bytes).unwrap();
+
+        // The embedded params aren't used, since the file on disk is wrong
+        let store = Store::new().with_path(&dir);
+        assert!(matches!(
+            store.read_params(ParameterSet::Six),
+            Err(Error::Checksum { .. })
+        ));
+    }
+
+    #[test]
+    fn proving_keys_round_trip() {
+        let dir = temp_dir("proving-keys");
+        let store = Store::new().with_path(&dir);
+        assert!(store
+            .read_proving_key(CircuitKind::Signature)
+            .unwrap()
+            .is_none());
+
+        write_keys(&dir, &[CircuitKind::Signature]).unwrap();
+
+        let bytes = store
+            .read_proving_key(CircuitKind::Signature)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            bytes,
+            CircuitKind::Signature
+                .pk()
+                .unwrap()
+                .to_bytes(SerdeFormat::RawBytes)
+        );
+
+        // A key that doesn't match the manifest is rejected
+        let path = dir.join(proving_key_file_name(CircuitKind::Signature));
+        fs::write(&path, &bytes[1..]).unwrap();
+        assert!(matches!(
+            store.read_proving_key(CircuitKind::Signature),
+            Err(Error::Checksum { .. })
+        ));
+
+        // As is a key that isn't in the manifest
+        fs::write(dir.join(MANIFEST_FILE_NAME), "{}").unwrap();
+        assert!(matches!(
+            store.read_proving_key(CircuitKind::Signature),
+            Err(Error::NotInManifest { .. })
+        ));
+    }
+}
@@ -4,8 +4,9 @@ use crate::{
     evm_verifier, CircuitKind,
 };
 use borsh::{BorshDeserialize, BorshSerialize};
+use halo2_base::halo2_proofs::halo2curves::bn256::Fr;
 use smirk::Element;
-use wire_message::{wire_message, WireMessage};
+use wire_message::wire_message;
 
 use super::fs::{load_file, load_witness, save_file, save_witness};
 
@@ -14,7 +15,10 @@ pub fn create_or_load_agg_agg_utxo_snark(params: ParameterSet, snarks: [Snark; 2
         .map(|sw| match sw {
             SnarkWitness::V1(sw) => sw,
         })
-        .map(|sw| sw.to_snark(CircuitKind::AggAgg.vk(), params))
+        .map(|sw| {
+            sw.to_snark(CircuitKind::AggAgg.vk().unwrap(), params)
+                .unwrap()
+        })
         .unwrap_or_else(|| {
             // Currently we can only do 1 for the Ethereum verifier as 2 creates a "too large" verifier (25,137 bytes) where
             // the max limit is 24,576 bytes (we are so close, we might be able to get this to fit!)
@@ -36,6 +40,7 @@ pub fn create_or_load_agg_agg_final_snark(params: ParameterSet, snark: Snark) ->
                 &AggregateAgg::<1>::new([snark.clone()]).keygen(params).1,
                 params,
             )
+            .unwrap()
         })
         .unwrap_or_else(|| {
             // Currently we can only do 1 for the Ethereum verifier as 2 creates a "too large" verifier (25,137 bytes) where
@@ -63,30 +68,13 @@ pub struct EvmProofV1 {
     pub utxo_inputs: Vec<Element>,
 }
 
-impl WireMessage for EvmProof {
-    type Ctx = ();
-    type Err = core::convert::Infallible;
-
-    fn upgrade_once(self, _ctx: &mut Self::Ctx) -> Result<Self, wire_message::Error> {
-        match self {
-            Self::V1(_) => Err(Self::max_version_error()),
-        }
-    }
-
-    fn version(&self) -> u64 {
-        match self {
-            Self::V1(_) => 1,
-        }
-    }
-}
-
 pub fn create_or_load_agg_agg_final_evm_proof(
     params: ParameterSet,
     agg_agg_utxo: Snark,
 ) -> EvmProof {
     load_file("agg_agg_final_evm_proof").unwrap_or_else(|| {
         let aggregate_agg_agg = AggregateAgg::<1>::new([agg_agg_utxo]);
-        let inputs = aggregate_agg_agg.public_inputs();
+        let inputs = aggregate_agg_agg.public_inputs().unwrap();
         let (pk, _) = aggregate_agg_agg.keygen(params);
 
         let proof =
@@ -100,8 +88,8 @@ pub fn create_or_load_agg_agg_final_evm_proof(
                 .cloned()
                 .map(From::from)
                 .collect(),
-            old_root: (*aggregate_agg_agg.old_root()).into(),
-            new_root: (*aggregate_agg_agg.new_root()).into(),
+            old_root: (*aggregate_agg_agg.old_root().unwrap()).into(),
+            new_root: (*aggregate_agg_agg.new_root().unwrap()).into(),
             utxo_inputs: aggregate_agg_agg
                 .utxo_values()
                 .into_iter()
@@ -115,3 +103,15 @@ pub fn create_or_load_agg_agg_final_evm_proof(
         evm_proof
     })
 }
+
+impl EvmProofV1 {
+    /// The public inputs of the proof, in the order of [`AggregateAgg::public_inputs`]
+    pub fn instances(&self) -> Vec<Fr> {
+        self.agg_instances
+            .iter()
+            .chain([&self.old_root, &self.new_root])
+            .chain(&self.utxo_inputs)
+            .map(|element| element.to_base())
+            .collect()
+    }
+}
@@ -30,7 +30,7 @@ pub fn load_agg_utxo_snarks<const N: usize>(params: ParameterSet) -> Option<[Sna
     for i in 0..N {
         let sw = load_witness(&format!("agg_utxo_{i}"))?;
         let SnarkWitness::V1(sw) = sw;
-        let snark = sw.to_snark(&UTXO_AGGREGATE_3_161_12_VK, params);
+        let snark = sw.to_snark(&UTXO_AGGREGATE_3_161_12_VK, params).unwrap();
         snarks.push(snark);
     }
     Some(snarks.try_into().unwrap())
@@ -50,6 +50,7 @@ pub fn default_aggregate_utxo() -> AggregateUtxo<UTXO_AGG_NUMBER, MERKLE_TREE_DE
         snarks.try_into().unwrap(),
         Batch::default(),
     )
+    .unwrap()
 }
 
 pub fn create_agg_utxo_snarks<const N: usize, const UTXO_N: usize>(
@@ -68,18 +68,29 @@ impl Rollup {
         &mut self,
         utxos: &[Utxo<MERKLE_TREE_DEPTH>; UTXO_AGG_NUMBER],
     ) -> Batch<UTXO_AGG_LEAVES, MERKLE_TREE_DEPTH> {
+        self.batch_inserts(utxos.iter().flat_map(|utxo| utxo.leafs()))
+    }
+
+    /// Inserts `leafs`, padding the batch with padding notes up to `LEAVES`
+    pub fn batch_inserts<const LEAVES: usize>(
+        &mut self,
+        leafs: impl IntoIterator<Item = Fr>,
+    ) -> Batch<LEAVES, MERKLE_TREE_DEPTH> {
+        let padding = Note::padding_note().commitment();
+
         let mut inserts = vec![];
-        for utxo in utxos.iter() {
-            for leaf in utxo.leafs() {
-                inserts.push(Insert::new(
-                    leaf.into(),
-                    merkle_path(&self.tree, leaf.into()),
-                ));
-                if Element::from(leaf) != Note::padding_note().commitment() {
-                    self.tree.insert(leaf.into(), ()).unwrap();
-                }
+        for leaf in leafs {
+            inserts.push(Insert::new(
+                leaf.into(),
+                merkle_path(&self.tree, leaf.into()),
+            ));
+            if Element::from(leaf) != padding {
+                self.tree.insert(leaf.into(), ()).unwrap();
             }
         }
+        while inserts.len() < LEAVES {
+            inserts.push(Insert::new(padding, merkle_path(&self.tree, padding)));
+        }
 
         Batch::new(inserts.try_into().unwrap())
     }
@@ -97,7 +108,7 @@ impl Rollup {
 
         let batch_inserts = self.batch_inserts_for_utxos(utxos);
 
-        AggregateUtxo::new(snarks.try_into().unwrap(), batch_inserts)
+        AggregateUtxo::new(snarks.try_into().unwrap(), batch_inserts).unwrap()
     }
 }
 
@@ -12,7 +12,7 @@ use halo2_base::halo2_proofs::{
     },
 };
 use num_bigint::{BigUint, ToBigUint};
-use serde::{Deserialize, Deserializer, Serializer};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use zk_primitives::Element;
 
 pub(crate) fn assign_private_input<F: FieldExt, V: Copy, N: Fn() -> NR, NR: Into<String>>(
@@ -72,11 +72,17 @@ pub fn insecure_random_element() -> Element {
     let mut rng: rand::rngs::ThreadRng = rand::thread_rng();
     Element::random(&mut rng).get_insecure()
 }
+/// Generate the keys for `circuit`
+///
+/// # Panics
+///
+/// If `params` can't be loaded. Callers that can recover should call [`load_params`] first
 pub(crate) fn keygen_from_params<C: Circuit<Fr>>(
     params: ParameterSet,
     circuit: &C,
 ) -> (ProvingKey<G1Affine>, VerifyingKey<G1Affine>) {
-    let params = load_params(params);
+    let params = load_params(params)
+        .unwrap_or_else(|err| panic!("failed to load {params:?} params: {err:?}"));
 
     let vk = keygen_vk(params, circuit).expect("keygen_vk should not fail");
     let pk = keygen_pk(params, vk.clone(), circuit).expect("keygen_pk should not fail");
@@ -116,6 +122,37 @@ where
         .map_err(serde::de::Error::custom)
 }
 
+// Custom serializer for arrays of any length (serde only supports arrays of up to 32 elements)
+pub fn serialize_array<S, T, const N: usize>(
+    value: &[T; N],
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    T: Serialize,
+{
+    serializer.collect_seq(value)
+}
+
+// An array of any length with no values, the default of an array of optional values
+pub fn none_array<T, const N: usize>() -> [Option<T>; N] {
+    std::array::from_fn(|_| None)
+}
+
+// Custom deserializer for arrays of any length, which checks the number of elements
+pub fn deserialize_array<'de, D, T, const N: usize>(deserializer: D) -> Result<[T; N], D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    let values = Vec::<T>::deserialize(deserializer)?;
+    let len = values.len();
+
+    values.try_into().map_err(|_| {
+        serde::de::Error::invalid_length(len, &format!("an array of length {N}").as_str())
+    })
+}
+
 pub fn serialize_hex_0x_prefixed<S>(value: &Vec<u8>, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
@@ -27,7 +27,9 @@ pub struct UtxoCircuitConfig {
     binary_decomposition_config: BinaryDecompositionConfig<Fr, 1>,
 }
 
-impl<const MERKLE_D: usize> Circuit<Fr> for Utxo<MERKLE_D> {
+impl<const MERKLE_D: usize, const INPUTS: usize, const OUTPUTS: usize> Circuit<Fr>
+    for Utxo<MERKLE_D, INPUTS, OUTPUTS>
+{
     type FloorPlanner = SimpleFloorPlanner;
     type Config = UtxoCircuitConfig;
 
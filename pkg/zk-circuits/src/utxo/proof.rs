@@ -1,19 +1,23 @@
 use crate::{
-    constants::{UTXO_INPUTS, UTXO_OUTPUTS},
     data::{ParameterSet, SnarkWitness, SnarkWitnessV1, UTXOProof, Utxo},
+    error::{Error, ErrorKind},
+    note_encryption::NoteCiphertext,
+    util::none_array,
     CircuitKind, Snark,
 };
 use primitives::hash::CryptoHash;
 use sha3::{Digest, Keccak256};
 use zk_primitives::Element;
 
-impl<const MERKLE_D: usize> UTXOProof<MERKLE_D> {
+impl<const MERKLE_D: usize, const INPUTS: usize, const OUTPUTS: usize>
+    UTXOProof<MERKLE_D, INPUTS, OUTPUTS>
+{
     pub fn new(
         recent_root: Element,
         mb_hash: Element,
         mb_value: Element,
-        input_leaves: [Element; UTXO_INPUTS],
-        output_leaves: [Element; UTXO_OUTPUTS],
+        input_leaves: [Element; INPUTS],
+        output_leaves: [Element; OUTPUTS],
         proof: Vec<u8>,
     ) -> Self {
         Self {
@@ -22,10 +26,40 @@ impl<const MERKLE_D: usize> UTXOProof<MERKLE_D> {
             mb_value,
             input_leaves,
             output_leaves,
+            output_memos: none_array(),
             proof,
         }
     }
 
+    /// Publish `output_memos` (e.g. from [`Utxo::output_memos`]) with the output leaves
+    ///
+    /// Returns an [`ErrorKind::InvalidInput`] error if a memo isn't for the output leaf at its
+    /// index
+    pub fn with_output_memos(
+        mut self,
+        output_memos: [Option<NoteCiphertext>; OUTPUTS],
+    ) -> crate::Result<Self> {
+        self.output_memos = output_memos;
+        self.check_output_memos()?;
+        Ok(self)
+    }
+
+    fn check_output_memos(&self) -> crate::Result<()> {
+        let mismatch = self
+            .output_memos
+            .iter()
+            .zip(self.output_leaves)
+            .position(|(memo, leaf)| memo.as_ref().is_some_and(|memo| memo.commitment != leaf));
+
+        match mismatch {
+            Some(index) => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("the memo of output {index} is for another commitment"),
+            )),
+            None => Ok(()),
+        }
+    }
+
     pub fn hash(&self) -> CryptoHash {
         let mut sorted_input_leaves = self.input_leaves;
         sorted_input_leaves.sort();
@@ -61,26 +95,42 @@ impl<const MERKLE_D: usize> UTXOProof<MERKLE_D> {
         self.is_mint_or_burn() && self.output_leaves.iter().all(|l| *l == Element::NULL_HASH)
     }
 
-    pub fn from_snark_witness(snark: SnarkWitness) -> Self {
+    /// Read the public inputs of a UTXO proof from a [`SnarkWitness`]
+    ///
+    /// Returns an [`ErrorKind::WrongInstanceCount`] error if the witness doesn't have the public
+    /// inputs of this shape of UTXO
+    pub fn from_snark_witness(snark: SnarkWitness) -> crate::Result<Self> {
         let SnarkWitness::V1(snark) = snark;
-        let instances = &snark.instances[0];
+
+        let expected = 3 + INPUTS + OUTPUTS;
+        let instances = match snark.instances.as_slice() {
+            [instances] if instances.len() == expected => instances,
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::WrongInstanceCount,
+                    format!("expected one instance column with {expected} instances"),
+                ))
+            }
+        };
+
         let recent_root = instances[0];
         let mb_hash = instances[1];
         let mb_value = instances[2];
-        let input_leaves = [instances[3], instances[4]];
-        let output_leaves = [instances[5], instances[6]];
-        Self {
+        let input_leaves = core::array::from_fn(|i| instances[3 + i]);
+        let output_leaves = core::array::from_fn(|i| instances[3 + INPUTS + i]);
+        Ok(Self {
             recent_root,
             mb_hash,
             mb_value,
             input_leaves,
             output_leaves,
+            output_memos: none_array(),
             proof: snark.proof,
-        }
+        })
     }
 
-    pub fn to_snark(&self, params: ParameterSet) -> Snark {
-        let utxo = Utxo::<MERKLE_D>::default();
+    pub fn to_snark(&self, params: ParameterSet) -> crate::Result<Snark> {
+        let utxo = Utxo::<MERKLE_D, INPUTS, OUTPUTS>::default();
         let (_, vk) = utxo.keygen(params);
 
         match self.to_snark_witness() {
@@ -107,9 +157,21 @@ impl<const MERKLE_D: usize> UTXOProof<MERKLE_D> {
             .collect()
     }
 
-    pub fn verify(&self) -> bool {
+    /// Verify this proof with the keys for its shape, and that each output memo is for its output
+    /// leaf
+    ///
+    /// Returns an [`ErrorKind::Other`] error if this shape of UTXO has no [`CircuitKind`]
+    pub fn verify(&self) -> crate::Result<()> {
+        self.check_output_memos()?;
+
+        let kind = Utxo::<MERKLE_D, INPUTS, OUTPUTS>::circuit_kind().ok_or_else(|| {
+            Error::err(format!(
+                "no circuit for a UTXO with {INPUTS} inputs and {OUTPUTS} outputs"
+            ))
+        })?;
+
         match self.to_snark_witness() {
-            SnarkWitness::V1(sw) => sw.verify(CircuitKind::Utxo),
+            SnarkWitness::V1(sw) => sw.verify(kind),
         }
     }
 }
@@ -119,7 +181,9 @@ mod tests {
     use crate::{
         constants::MERKLE_TREE_DEPTH,
         data::{InputNote, Note, UtxoKind},
+        note_encryption::{encrypt, ViewingKey},
     };
+    use rand::thread_rng;
 
     use super::*;
 
@@ -134,15 +198,90 @@ mod tests {
 
         let snark = u.snark(CircuitKind::Utxo).unwrap();
 
-        assert!(snark.to_witness().verify(CircuitKind::Utxo));
+        snark.to_witness().verify(CircuitKind::Utxo).unwrap();
 
-        let utxo_proof = UTXOProof::<161>::from_snark_witness(SnarkWitness::V1(snark.to_witness()));
-        assert!(utxo_proof.verify());
+        let utxo_proof =
+            UTXOProof::<161>::from_snark_witness(SnarkWitness::V1(snark.to_witness())).unwrap();
+        utxo_proof.verify().unwrap();
 
         let snark_witness = utxo_proof.to_snark_witness();
         println!("{}", serde_json::to_string(&snark_witness).unwrap());
     }
 
+    #[test]
+    fn malformed_proofs_are_errors() {
+        let snark = Utxo::<161>::new_padding().snark(CircuitKind::Utxo).unwrap();
+
+        let mut truncated = snark.to_witness();
+        truncated.proof.truncate(100);
+        let err = truncated.verify(CircuitKind::Utxo).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidProof);
+
+        let mut extra_column = snark.to_witness();
+        extra_column.instances.push(vec![]);
+        let err = extra_column.verify(CircuitKind::Utxo).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WrongInstanceCount);
+
+        let mut missing_leaf = snark.to_witness();
+        missing_leaf.instances[0].pop();
+        let err = UTXOProof::<161>::from_snark_witness(SnarkWitness::V1(missing_leaf)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WrongInstanceCount);
+    }
+
+    #[test]
+    fn batch_verification_finds_invalid_proofs() {
+        let witnesses = (0..4)
+            .map(|_| {
+                Utxo::<161>::new_padding()
+                    .snark(CircuitKind::Utxo)
+                    .unwrap()
+                    .to_witness()
+            })
+            .collect::<Vec<_>>();
+
+        let invalid = SnarkWitnessV1::verify_batch(CircuitKind::Utxo, &witnesses).unwrap();
+        assert!(invalid.is_empty());
+
+        let mut witnesses = witnesses;
+        witnesses[1].instances[0][0] = witnesses[1].instances[0][0] + Element::ONE;
+        witnesses[2].proof.truncate(100);
+        witnesses[3].instances.push(vec![]);
+
+        let invalid = SnarkWitnessV1::verify_batch(CircuitKind::Utxo, &witnesses).unwrap();
+        assert_eq!(invalid, [1, 2, 3]);
+    }
+
+    #[test]
+    fn output_memos_are_for_their_leaves() {
+        let viewing_key = ViewingKey::random(&mut thread_rng());
+        let note = Note::new(Element::new(1), Element::new(10));
+        let memo = encrypt(&note, &viewing_key.public_key(), &mut thread_rng());
+
+        let txn = UTXOProof::<MERKLE_TREE_DEPTH>::new(
+            Element::new(1),
+            Element::NULL_HASH,
+            Element::NULL_HASH,
+            [Element::new(5), Element::new(6)],
+            [note.commitment(), Element::NULL_HASH],
+            vec![],
+        );
+
+        let err = txn
+            .clone()
+            .with_output_memos([None, Some(memo.clone())])
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+        let txn = txn.with_output_memos([Some(memo.clone()), None]).unwrap();
+        assert_eq!(txn.output_memos, [Some(memo), None]);
+
+        // Txns serialized before they had memos have none
+        let mut json = serde_json::to_value(&txn).unwrap();
+        json.as_object_mut().unwrap().remove("output_memos");
+        let txn = serde_json::from_value::<UTXOProof<MERKLE_TREE_DEPTH>>(json).unwrap();
+        assert_eq!(txn.output_memos, [None, None]);
+    }
+
     #[test]
     fn bench_txn_hashing() {
         let txn = UTXOProof::<MERKLE_TREE_DEPTH>::new(
@@ -1,6 +1,7 @@
 use crate::{
     constants::MERKLE_TREE_DEPTH,
     data::{InputNote, Note, Utxo, UtxoKind},
+    note_encryption::{try_decrypt, ViewingKey},
     test::rollup::Rollup,
     CircuitKind,
 };
@@ -44,7 +45,7 @@ fn test_utxo_one_input_one_output() {
 
     // Prove for real circuit
     let snark = circuit.snark(CircuitKind::Utxo).unwrap();
-    assert!(snark.to_witness().verify(CircuitKind::Utxo));
+    snark.to_witness().verify(CircuitKind::Utxo).unwrap();
 }
 
 #[test]
@@ -145,3 +146,72 @@ fn test_utxo_burn() {
     let prover = MockProver::<Fr>::run(k, &circuit, instance_columns).unwrap();
     prover.assert_satisfied();
 }
+
+#[test]
+fn test_utxo_four_inputs_one_output() {
+    let k = 15;
+
+    let mut rollup = Rollup::new();
+    let bob = rollup.new_wallet();
+    let alice = rollup.new_wallet();
+
+    // Consolidate four of bob's notes into one note for alice
+    let bob_notes = [10, 20, 30, 40].map(|amount| rollup.unverified_add_unspent_note(&bob, amount));
+    let recent_root = rollup.root_hash();
+
+    let input_notes = [
+        rollup.to_input_note(&bob_notes[0]),
+        rollup.to_input_note(&bob_notes[1]),
+        rollup.to_input_note(&bob_notes[2]),
+        rollup.to_input_note(&bob_notes[3]),
+    ];
+
+    let output_note = alice.new_note(100);
+    let output_notes = [output_note.clone(), Note::padding_note()];
+
+    let circuit = Utxo::<MERKLE_TREE_DEPTH, 4, 2>::new(
+        input_notes.clone(),
+        output_notes,
+        recent_root,
+        UtxoKind::Transfer,
+    );
+    let public_input = circuit.public_inputs();
+
+    assert_eq!(public_input.len(), 9);
+    assert_eq!(public_input[0], recent_root.to_base());
+    for (i, input_note) in input_notes.iter().enumerate() {
+        assert_eq!(public_input[3 + i], input_note.nullifer().into());
+    }
+    assert_eq!(public_input[7], output_note.commitment().into());
+    assert_eq!(public_input[8], Fr::zero());
+
+    let prover = MockProver::<Fr>::run(k, &circuit, vec![public_input]).unwrap();
+    prover.assert_satisfied();
+
+    assert_eq!(
+        Utxo::<MERKLE_TREE_DEPTH, 4, 2>::circuit_kind(),
+        Some(CircuitKind::Utxo4x2)
+    );
+}
+
+#[test]
+fn test_utxo_output_memos() {
+    let mut rollup = Rollup::new();
+    let bob = rollup.new_wallet();
+    let alice = rollup.new_wallet();
+    let alice_viewing_key = ViewingKey::random(&mut rand::thread_rng());
+
+    let bob_note = rollup.unverified_add_unspent_note(&bob, 10);
+    let output_note = alice.new_note(10);
+
+    let circuit = rollup.transfer(bob_note, output_note.clone());
+    let [memo, padding_memo] = circuit.output_memos(
+        &[Some(alice_viewing_key.public_key()), None],
+        &mut rand::thread_rng(),
+    );
+
+    let memo = memo.unwrap();
+    assert_eq!(memo.commitment, output_note.commitment());
+    assert_eq!(try_decrypt(&alice_viewing_key, &memo), Some(output_note));
+    assert_eq!(padding_memo, None);
+}
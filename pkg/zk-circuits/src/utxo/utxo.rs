@@ -6,8 +6,8 @@ use crate::{
         binary_decomposition::BinaryDecompositionConfig, is_constant::IsConstantChip,
         poseidon::PoseidonConfig, swap::CondSwapChip,
     },
-    constants::{UTXO_INPUTS, UTXO_OUTPUTS},
     data::{InputNote, Note, ParameterSet, Utxo, UtxoKind},
+    note_encryption::{self, NoteCiphertext, PublicViewingKey},
     params::load_params,
     proof::Proof,
     util::{assign_constant, assign_private_input, keygen_from_params},
@@ -19,7 +19,7 @@ use halo2_base::halo2_proofs::{
     plonk::{Advice, Column, Error, Instance, ProvingKey, VerifyingKey},
     poly::kzg::commitment::ParamsKZG,
 };
-use rand::RngCore;
+use rand::{CryptoRng, RngCore};
 use zk_primitives::Element;
 
 impl UtxoKind {
@@ -33,10 +33,12 @@ impl UtxoKind {
     }
 }
 
-impl<const MERKLE_D: usize> Utxo<MERKLE_D> {
+impl<const MERKLE_D: usize, const INPUTS: usize, const OUTPUTS: usize>
+    Utxo<MERKLE_D, INPUTS, OUTPUTS>
+{
     pub fn new(
-        inputs: [InputNote<MERKLE_D>; UTXO_INPUTS],
-        outputs: [Note; UTXO_OUTPUTS],
+        inputs: [InputNote<MERKLE_D>; INPUTS],
+        outputs: [Note; OUTPUTS],
         root: Element,
         kind: UtxoKind,
     ) -> Self {
@@ -49,8 +51,8 @@ impl<const MERKLE_D: usize> Utxo<MERKLE_D> {
     }
 
     pub fn new_transfer(
-        inputs: [InputNote<MERKLE_D>; UTXO_INPUTS],
-        outputs: [Note; UTXO_OUTPUTS],
+        inputs: [InputNote<MERKLE_D>; INPUTS],
+        outputs: [Note; OUTPUTS],
         root: Element,
     ) -> Self {
         Utxo::new(inputs, outputs, root, UtxoKind::Transfer)
@@ -59,18 +61,20 @@ impl<const MERKLE_D: usize> Utxo<MERKLE_D> {
     // TODO: do we need root here? Surely its just a padding element
     pub fn new_mint(output_note: Note) -> Self {
         let inputs = array::from_fn(|_| InputNote::padding_note());
-        let outputs = [output_note, Note::padding_note()];
+        let mut output_note = Some(output_note);
+        let outputs = array::from_fn(|_| output_note.take().unwrap_or_else(Note::padding_note));
         Utxo::new(inputs, outputs, Element::ZERO, UtxoKind::Mint)
     }
 
     pub fn new_burn(input_note: InputNote<MERKLE_D>, root: Element) -> Self {
-        let inputs = [input_note, InputNote::padding_note()];
+        let mut input_note = Some(input_note);
+        let inputs = array::from_fn(|_| input_note.take().unwrap_or_else(InputNote::padding_note));
         let outputs = array::from_fn(|_| Note::padding_note());
         Utxo::new(inputs, outputs, root, UtxoKind::Burn)
     }
 
     pub fn new_padding() -> Self {
-        let inputs: [InputNote<MERKLE_D>; 2] = array::from_fn(|_| InputNote::padding_note());
+        let inputs = array::from_fn(|_| InputNote::padding_note());
         let outputs = array::from_fn(|_| Note::padding_note());
         Utxo::new(inputs, outputs, Element::ZERO, UtxoKind::Transfer)
     }
@@ -331,28 +335,46 @@ impl<const MERKLE_D: usize> Utxo<MERKLE_D> {
         hashes
     }
 
+    /// Encrypt each output note to its recipient's viewing key, so the ciphertexts can be
+    /// published alongside the output commitments (see
+    /// [`UTXOProof::with_output_memos`][crate::data::UTXOProof::with_output_memos])
+    ///
+    /// The ciphertext is `None` for padding notes, and for outputs without a recipient
+    pub fn output_memos(
+        &self,
+        recipients: &[Option<PublicViewingKey>; OUTPUTS],
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> [Option<NoteCiphertext>; OUTPUTS] {
+        array::from_fn(|i| {
+            let note = &self.outputs[i];
+            let recipient = recipients[i].as_ref().filter(|_| !note.is_padding())?;
+
+            Some(note_encryption::encrypt(note, recipient, rng))
+        })
+    }
+
     pub fn prove(
         &self,
         params: &ParamsKZG<Bn256>,
         pk: &ProvingKey<G1Affine>,
         rng: impl RngCore,
-    ) -> Result<Proof, Error> {
+    ) -> crate::Result<Proof> {
         let circuit = Self::default();
         let instance = self.public_inputs();
         let instances = &[instance.as_slice()];
         Proof::create(params, pk, circuit, instances, rng)
     }
 
-    pub fn snark(&self, kind: CircuitKind) -> Result<Snark, crate::Error> {
+    /// The [`CircuitKind`] of this shape of UTXO, or `None` if this shape has no keys
+    pub fn circuit_kind() -> Option<CircuitKind> {
+        CircuitKind::utxo(INPUTS, OUTPUTS)
+    }
+
+    pub fn snark(&self, kind: CircuitKind) -> crate::Result<Snark> {
+        let kzg_params = load_params(kind.params())?;
         let (pk, _) = self.keygen(kind.params());
 
-        Snark::create(
-            self.clone(),
-            vec![self.public_inputs()],
-            load_params(kind.params()),
-            &pk,
-        )
-        .map_err(crate::Error::err)
+        Snark::create(self.clone(), vec![self.public_inputs()], kzg_params, &pk)
     }
 
     pub fn keygen(&self, params: ParameterSet) -> (ProvingKey<G1Affine>, VerifyingKey<G1Affine>) {
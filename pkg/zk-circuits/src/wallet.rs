@@ -0,0 +1,663 @@
+//! A wallet that tracks the notes owned by a [`SpendingKey`], and builds [`Utxo`] transactions
+//! that spend them
+//!
+//! The wallet only knows about the notes it has been given (with [`Wallet::add_note`] or
+//! [`Wallet::receive`]), it doesn't watch the tree. Building a transaction doesn't change the
+//! wallet's notes, once the transaction is in the tree, call [`Wallet::confirm`] to remove the
+//! spent notes and add any change.
+//!
+//! ```text
+//! let mut wallet = Wallet::new(seed.spending_key());
+//! wallet.receive(&ciphertext);
+//!
+//! let txn = wallet.transfer::<2, 2>(&recipient, value, &tree)?;
+//! let proof = txn.prove(&mut rng)?;
+//! // submit the proof, which carries the memos, and wait for it to be in the tree
+//! wallet.confirm(&txn);
+//! ```
+
+use crate::{
+    constants::MERKLE_TREE_DEPTH,
+    data::{InputNote, MerklePath, Note, SnarkWitness, UTXOProof, Utxo, UtxoKind},
+    key_derivation::{AddressKey, PaymentAddress, SpendingKey},
+    note_encryption::{NoteCiphertext, PublicViewingKey},
+    params::load_params,
+    Snark,
+};
+use rand::{CryptoRng, RngCore};
+use smirk::Tree;
+use std::array;
+use zk_primitives::Element;
+
+/// The number of addresses after the last used address that [`Wallet::receive`] checks, so a
+/// wallet restored from its seed finds notes sent to addresses it hasn't handed out yet
+pub const ADDRESS_LOOKAHEAD: u64 = 20;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("the value of a transfer must not be zero")]
+    ZeroValue,
+    #[error("insufficient funds: required {required}, available {available}")]
+    InsufficientFunds {
+        required: Element,
+        available: Element,
+    },
+    #[error("{needed} notes are needed to cover the value, but the txn has {max} inputs")]
+    TooManyInputs { needed: usize, max: usize },
+    #[error("the txn needs {needed} outputs, but only has {max}")]
+    TooManyOutputs { needed: usize, max: usize },
+    #[error("no note with value {value} to burn")]
+    NoExactNote { value: Element },
+    #[error("note {commitment} is not in the tree")]
+    NoteNotInTree { commitment: Element },
+    #[error("there are no keys for a UTXO with {inputs} inputs and {outputs} outputs")]
+    UnsupportedShape { inputs: usize, outputs: usize },
+    #[error("failed to create proof: {0}")]
+    Proof(#[from] crate::Error),
+}
+
+/// A note owned by the wallet, with the key of the address it was sent to
+#[derive(Debug, Clone, PartialEq)]
+pub struct OwnedNote {
+    pub note: Note,
+    pub key: AddressKey,
+}
+
+impl OwnedNote {
+    pub fn new(note: Note, key: AddressKey) -> Self {
+        Self { note, key }
+    }
+
+    pub fn commitment(&self) -> Element {
+        self.note.commitment()
+    }
+
+    pub fn nullifier(&self) -> Element {
+        self.key.nullifier(&self.note)
+    }
+
+    /// The input note to spend this note in a txn against `tree`
+    pub fn to_input_note(
+        &self,
+        tree: &Tree<MERKLE_TREE_DEPTH, ()>,
+    ) -> Result<InputNote<MERKLE_TREE_DEPTH>, Error> {
+        let commitment = self.commitment();
+        if !tree.contains_element(commitment) {
+            return Err(Error::NoteNotInTree { commitment });
+        }
+
+        let path = tree.path_for(commitment);
+        let merkle_path = MerklePath::new(path.siblings_deepest_first().to_vec());
+
+        Ok(InputNote::new(
+            self.note.clone(),
+            self.key.secret_key(),
+            merkle_path,
+        ))
+    }
+}
+
+/// A txn built by a [`Wallet`], which hasn't been proven yet
+#[derive(Debug, Clone)]
+pub struct Transaction<const INPUTS: usize, const OUTPUTS: usize> {
+    pub utxo: Utxo<MERKLE_TREE_DEPTH, INPUTS, OUTPUTS>,
+    /// The notes spent by this txn
+    pub spent: Vec<OwnedNote>,
+    /// The notes created for the wallet by this txn (change, or a minted note)
+    pub received: Vec<OwnedNote>,
+    /// The viewing key that each output is encrypted to, see [`Transaction::memos`]
+    pub recipients: [Option<PublicViewingKey>; OUTPUTS],
+}
+
+impl<const INPUTS: usize, const OUTPUTS: usize> Transaction<INPUTS, OUTPUTS> {
+    /// Prove the txn with the keys for its shape, which are only generated (or loaded from the
+    /// [store][crate::store]) on first use. The proof carries the [memos][Transaction::memos]
+    pub fn prove(
+        &self,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Result<UTXOProof<MERKLE_TREE_DEPTH, INPUTS, OUTPUTS>, Error> {
+        let kind = Utxo::<MERKLE_TREE_DEPTH, INPUTS, OUTPUTS>::circuit_kind().ok_or(
+            Error::UnsupportedShape {
+                inputs: INPUTS,
+                outputs: OUTPUTS,
+            },
+        )?;
+
+        let snark = Snark::create(
+            self.utxo.clone(),
+            vec![self.utxo.public_inputs()],
+            load_params(kind.params())?,
+            kind.pk()?,
+        )?;
+
+        let proof = UTXOProof::from_snark_witness(SnarkWitness::V1(snark.to_witness()))?
+            .with_output_memos(self.memos(rng))?;
+
+        Ok(proof)
+    }
+
+    /// The encrypted output notes, which are published with the proof
+    pub fn memos(&self, rng: &mut (impl RngCore + CryptoRng)) -> [Option<NoteCiphertext>; OUTPUTS] {
+        self.utxo.output_memos(&self.recipients, rng)
+    }
+}
+
+/// The notes owned by a [`SpendingKey`]
+#[derive(Debug, Clone)]
+pub struct Wallet {
+    spending_key: SpendingKey,
+    /// The index of the next unused address
+    next_address: u64,
+    notes: Vec<OwnedNote>,
+}
+
+impl Wallet {
+    pub fn new(spending_key: SpendingKey) -> Self {
+        Self {
+            spending_key,
+            next_address: 0,
+            notes: vec![],
+        }
+    }
+
+    pub fn spending_key(&self) -> &SpendingKey {
+        &self.spending_key
+    }
+
+    /// The key for a fresh address, which hasn't been used by this wallet before
+    pub fn next_address_key(&mut self) -> AddressKey {
+        let key = self.spending_key.address_key(self.next_address);
+        self.next_address += 1;
+        key
+    }
+
+    /// A fresh address to share with a sender
+    pub fn next_payment_address(&mut self) -> PaymentAddress {
+        let index = self.next_address_key().index();
+        self.spending_key.payment_address(index)
+    }
+
+    pub fn notes(&self) -> &[OwnedNote] {
+        &self.notes
+    }
+
+    /// Track a note sent to one of the wallet's addresses
+    pub fn add_note(&mut self, note: OwnedNote) {
+        if !note.note.is_padding() && !self.notes.contains(&note) {
+            self.notes.push(note);
+        }
+    }
+
+    /// Track the note in `ciphertext`, if it was sent to one of the wallet's addresses
+    ///
+    /// Each address has its own viewing key, so only the addresses that have been handed out and
+    /// the next [`ADDRESS_LOOKAHEAD`] addresses are checked. A note to one of the lookahead
+    /// addresses marks it, and every address before it, as used
+    pub fn receive(&mut self, ciphertext: &NoteCiphertext) -> Option<&OwnedNote> {
+        let full_viewing_key = self.spending_key.full_viewing_key();
+
+        let (note, key) = (0..self.next_address + ADDRESS_LOOKAHEAD).find_map(|index| {
+            let note = full_viewing_key.try_decrypt(index, ciphertext)?;
+            let key = self.spending_key.address_key(index);
+
+            (key.address() == note.address()).then_some((note, key))
+        })?;
+
+        self.next_address = self.next_address.max(key.index() + 1);
+
+        self.add_note(OwnedNote::new(note, key));
+        self.notes
+            .iter()
+            .find(|owned| owned.commitment() == ciphertext.commitment)
+    }
+
+    /// The total value of the wallet's notes
+    pub fn balance(&self) -> Element {
+        self.notes.iter().map(|owned| owned.note.value()).sum()
+    }
+
+    /// Remove the notes spent by `txn`, and track the notes it created for the wallet
+    ///
+    /// This should only be called once the txn is in the tree
+    pub fn confirm<const INPUTS: usize, const OUTPUTS: usize>(
+        &mut self,
+        txn: &Transaction<INPUTS, OUTPUTS>,
+    ) {
+        self.notes.retain(|owned| !txn.spent.contains(owned));
+
+        for note in &txn.received {
+            self.add_note(note.clone());
+        }
+    }
+
+    /// Select at most `max` notes with a total value of at least `value`
+    ///
+    /// The largest notes are selected first, so the fewest inputs are used
+    pub fn select_notes(&self, value: Element, max: usize) -> Result<Vec<OwnedNote>, Error> {
+        let available = self.balance();
+        if available < value {
+            return Err(Error::InsufficientFunds {
+                required: value,
+                available,
+            });
+        }
+
+        let mut notes = self.notes.iter().collect::<Vec<_>>();
+        notes.sort_by_key(|owned| core::cmp::Reverse(owned.note.value()));
+
+        let mut total = Element::ZERO;
+        let selected = notes
+            .into_iter()
+            .take_while(|owned| {
+                let needed = total < value;
+                total = total + owned.note.value();
+                needed
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if selected.len() > max {
+            return Err(Error::TooManyInputs {
+                needed: selected.len(),
+                max,
+            });
+        }
+
+        Ok(selected)
+    }
+
+    /// Send `value` to `to`, with any change sent to a fresh address of the wallet
+    ///
+    /// Returns [`Error::ZeroValue`] if `value` is zero, as the output would be a padding note
+    pub fn transfer<const INPUTS: usize, const OUTPUTS: usize>(
+        &mut self,
+        to: &PaymentAddress,
+        value: Element,
+        tree: &Tree<MERKLE_TREE_DEPTH, ()>,
+    ) -> Result<Transaction<INPUTS, OUTPUTS>, Error> {
+        if value == Element::ZERO {
+            return Err(Error::ZeroValue);
+        }
+
+        let spent = self.select_notes(value, INPUTS)?;
+        let inputs = input_notes(&spent, tree)?;
+
+        let total = spent
+            .iter()
+            .map(|owned| owned.note.value())
+            .sum::<Element>();
+        let change = total - value;
+
+        let needed = if change == Element::ZERO { 1 } else { 2 };
+        if needed > OUTPUTS {
+            return Err(Error::TooManyOutputs {
+                needed,
+                max: OUTPUTS,
+            });
+        }
+
+        // The source of the outputs is the address of the first input
+        let mut outputs = vec![(inputs[0].output_note(to.address, value), to.viewing_key)];
+        let mut received = vec![];
+
+        if change != Element::ZERO {
+            let key = self.next_address_key();
+            let change_address = self.spending_key.payment_address(key.index());
+            let note = inputs[0].output_note(change_address.address, change);
+
+            outputs.push((note.clone(), change_address.viewing_key));
+            received.push(OwnedNote::new(note, key));
+        }
+
+        let mut outputs = outputs.into_iter();
+        let (outputs, recipients) = padded(|| outputs.next());
+
+        Ok(Transaction {
+            utxo: Utxo::new(inputs, outputs, tree.root_hash(), UtxoKind::Transfer),
+            spent,
+            received,
+            recipients,
+        })
+    }
+
+    /// Mint a note of `value` to a fresh address of the wallet
+    pub fn mint<const INPUTS: usize, const OUTPUTS: usize>(
+        &mut self,
+        value: Element,
+    ) -> Transaction<INPUTS, OUTPUTS> {
+        let key = self.next_address_key();
+        let note = Note::new(key.address(), value);
+
+        let mut recipients = [None; OUTPUTS];
+        recipients[0] = Some(self.spending_key.payment_address(key.index()).viewing_key);
+
+        Transaction {
+            utxo: Utxo::new_mint(note.clone()),
+            spent: vec![],
+            received: vec![OwnedNote::new(note, key)],
+            recipients,
+        }
+    }
+
+    /// Burn a note of exactly `value`
+    ///
+    /// A burn can't have change, so if the wallet doesn't have a note with this value, a note
+    /// must first be split off with [`Wallet::split`]
+    pub fn burn<const INPUTS: usize, const OUTPUTS: usize>(
+        &self,
+        value: Element,
+        tree: &Tree<MERKLE_TREE_DEPTH, ()>,
+    ) -> Result<Transaction<INPUTS, OUTPUTS>, Error> {
+        let note = self
+            .notes
+            .iter()
+            .find(|owned| owned.note.value() == value)
+            .ok_or(Error::NoExactNote { value })?;
+
+        Ok(Transaction {
+            utxo: Utxo::new_burn(note.to_input_note(tree)?, tree.root_hash()),
+            spent: vec![note.clone()],
+            received: vec![],
+            recipients: [None; OUTPUTS],
+        })
+    }
+
+    /// Transfer `value` to a fresh address of the wallet, so there is a note with
+    /// exactly that value (e.g. to [burn][Wallet::burn])
+    pub fn split<const INPUTS: usize, const OUTPUTS: usize>(
+        &mut self,
+        value: Element,
+        tree: &Tree<MERKLE_TREE_DEPTH, ()>,
+    ) -> Result<Transaction<INPUTS, OUTPUTS>, Error> {
+        let key = self.next_address_key();
+        let to = self.spending_key.payment_address(key.index());
+
+        // Only use up the address if the txn can be built
+        let txn = self.transfer(&to, value, tree);
+        if txn.is_err() {
+            self.next_address = key.index();
+        }
+        let mut txn = txn?;
+
+        let note = txn.utxo.outputs[0].clone();
+        txn.received.insert(0, OwnedNote::new(note, key));
+
+        Ok(txn)
+    }
+}
+
+fn input_notes<const INPUTS: usize>(
+    notes: &[OwnedNote],
+    tree: &Tree<MERKLE_TREE_DEPTH, ()>,
+) -> Result<[InputNote<MERKLE_TREE_DEPTH>; INPUTS], Error> {
+    let mut inputs = notes
+        .iter()
+        .map(|owned| owned.to_input_note(tree))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter();
+
+    Ok(array::from_fn(|_| {
+        inputs.next().unwrap_or_else(InputNote::padding_note)
+    }))
+}
+
+/// Fill the outputs from `next`, padding the rest
+fn padded<const OUTPUTS: usize>(
+    mut next: impl FnMut() -> Option<(Note, PublicViewingKey)>,
+) -> ([Note; OUTPUTS], [Option<PublicViewingKey>; OUTPUTS]) {
+    let mut recipients = [None; OUTPUTS];
+    let outputs = array::from_fn(|i| match next() {
+        Some((note, recipient)) => {
+            recipients[i] = Some(recipient);
+            note
+        }
+        None => Note::padding_note(),
+    });
+
+    (outputs, recipients)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key_derivation::Seed;
+    use halo2_base::halo2_proofs::{dev::MockProver, halo2curves::bn256::Fr};
+    use rand::thread_rng;
+
+    /// A wallet with a note of each of `values`, which are all in `tree`
+    fn funded_wallet(values: &[u64], tree: &mut Tree<MERKLE_TREE_DEPTH, ()>) -> Wallet {
+        let mut wallet = Wallet::new(Seed::random(&mut thread_rng()).spending_key());
+
+        for value in values {
+            let txn = wallet.mint::<2, 2>(Element::from(*value));
+            tree.insert(txn.utxo.outputs[0].commitment(), ()).unwrap();
+            wallet.confirm(&txn);
+        }
+
+        wallet
+    }
+
+    fn recipient() -> PaymentAddress {
+        Seed::random(&mut thread_rng())
+            .spending_key()
+            .payment_address(0)
+    }
+
+    fn assert_satisfied<const INPUTS: usize, const OUTPUTS: usize>(
+        txn: &Transaction<INPUTS, OUTPUTS>,
+    ) {
+        // Larger shapes need more rows
+        let k = if INPUTS > 2 { 15 } else { 14 };
+
+        let prover = MockProver::<Fr>::run(k, &txn.utxo, vec![txn.utxo.public_inputs()]).unwrap();
+        prover.assert_satisfied();
+    }
+
+    #[test]
+    fn selects_largest_notes_first() {
+        let mut tree = Tree::new();
+        let wallet = funded_wallet(&[5, 20, 10], &mut tree);
+
+        let selected = wallet.select_notes(Element::from(25u64), 2).unwrap();
+        let values = selected
+            .iter()
+            .map(|owned| owned.note.value())
+            .collect::<Vec<_>>();
+
+        assert_eq!(values, [Element::from(20u64), Element::from(10u64)]);
+    }
+
+    #[test]
+    fn selection_errors() {
+        let mut tree = Tree::new();
+        let wallet = funded_wallet(&[5, 5, 5], &mut tree);
+
+        assert!(matches!(
+            wallet.select_notes(Element::from(16u64), 4),
+            Err(Error::InsufficientFunds { .. })
+        ));
+        assert!(matches!(
+            wallet.select_notes(Element::from(15u64), 2),
+            Err(Error::TooManyInputs { needed: 3, max: 2 })
+        ));
+    }
+
+    #[test]
+    fn transfer_with_change() {
+        let mut tree = Tree::new();
+        let mut wallet = funded_wallet(&[10, 20], &mut tree);
+        let to = recipient();
+
+        let txn = wallet
+            .transfer::<2, 2>(&to, Element::from(25u64), &tree)
+            .unwrap();
+        assert_satisfied(&txn);
+
+        assert_eq!(txn.utxo.outputs[0].address(), to.address);
+        assert_eq!(txn.utxo.outputs[0].value(), Element::from(25u64));
+        assert_eq!(txn.utxo.outputs[1].value(), Element::from(5u64));
+        assert_eq!(txn.recipients[0], Some(to.viewing_key));
+
+        for output in &txn.utxo.outputs {
+            tree.insert(output.commitment(), ()).unwrap();
+        }
+        wallet.confirm(&txn);
+
+        assert_eq!(wallet.notes().len(), 1);
+        assert_eq!(wallet.balance(), Element::from(5u64));
+    }
+
+    #[test]
+    fn zero_value_transfer_is_rejected() {
+        let mut tree = Tree::new();
+        let mut wallet = funded_wallet(&[10], &mut tree);
+
+        assert!(matches!(
+            wallet.transfer::<2, 2>(&recipient(), Element::ZERO, &tree),
+            Err(Error::ZeroValue)
+        ));
+        assert!(matches!(
+            wallet.split::<2, 2>(Element::ZERO, &tree),
+            Err(Error::ZeroValue)
+        ));
+    }
+
+    #[test]
+    fn failed_split_keeps_address() {
+        let mut tree = Tree::new();
+        let mut wallet = funded_wallet(&[10], &mut tree);
+        let next_address = wallet.next_address;
+
+        assert!(matches!(
+            wallet.split::<2, 2>(Element::from(20u64), &tree),
+            Err(Error::InsufficientFunds { .. })
+        ));
+        assert_eq!(wallet.next_address, next_address);
+    }
+
+    #[test]
+    fn transfer_pads_inputs_and_outputs() {
+        let mut tree = Tree::new();
+        let mut wallet = funded_wallet(&[10, 20], &mut tree);
+
+        let txn = wallet
+            .transfer::<4, 2>(&recipient(), Element::from(20u64), &tree)
+            .unwrap();
+        assert_satisfied(&txn);
+
+        assert!(txn.utxo.inputs[1..].iter().all(InputNote::is_padding));
+        assert!(txn.utxo.outputs[1].is_padding());
+        assert_eq!(txn.recipients[1], None);
+        assert!(txn.received.is_empty());
+    }
+
+    #[test]
+    fn received_memos_are_spendable() {
+        let mut tree = Tree::new();
+        let mut sender = funded_wallet(&[10], &mut tree);
+        let mut receiver = Wallet::new(Seed::random(&mut thread_rng()).spending_key());
+        let to = receiver.next_payment_address();
+
+        let txn = sender
+            .transfer::<2, 2>(&to, Element::from(7u64), &tree)
+            .unwrap();
+        let memos = txn.memos(&mut thread_rng());
+
+        for output in &txn.utxo.outputs {
+            tree.insert(output.commitment(), ()).unwrap();
+        }
+
+        assert!(receiver.receive(memos[0].as_ref().unwrap()).is_some());
+        assert!(receiver.receive(memos[1].as_ref().unwrap()).is_none());
+        assert_eq!(receiver.balance(), Element::from(7u64));
+
+        let txn = receiver
+            .transfer::<2, 2>(&recipient(), Element::from(7u64), &tree)
+            .unwrap();
+        assert_satisfied(&txn);
+    }
+
+    #[test]
+    fn restored_wallet_receives_with_lookahead() {
+        let seed = Seed::random(&mut thread_rng());
+        let spending_key = seed.spending_key();
+        let mut restored = Wallet::new(seed.spending_key());
+
+        let (note, memo) = spending_key
+            .payment_address(3)
+            .new_note(Element::from(10u64), &mut thread_rng());
+        let owned = restored.receive(&memo).unwrap();
+        assert_eq!(owned.note, note);
+        assert_eq!(owned.key.index(), 3);
+        assert_eq!(restored.next_address, 4);
+
+        // Addresses past the lookahead aren't checked
+        let (_, far_memo) = spending_key
+            .payment_address(4 + ADDRESS_LOOKAHEAD)
+            .new_note(Element::from(10u64), &mut thread_rng());
+        assert!(restored.receive(&far_memo).is_none());
+    }
+
+    #[test]
+    fn split_then_burn() {
+        let mut tree = Tree::new();
+        let mut wallet = funded_wallet(&[10], &mut tree);
+
+        assert!(matches!(
+            wallet.burn::<2, 2>(Element::from(4u64), &tree),
+            Err(Error::NoExactNote { .. })
+        ));
+
+        let split = wallet.split::<2, 2>(Element::from(4u64), &tree).unwrap();
+        assert_satisfied(&split);
+
+        for output in &split.utxo.outputs {
+            tree.insert(output.commitment(), ()).unwrap();
+        }
+        wallet.confirm(&split);
+        assert_eq!(wallet.notes().len(), 2);
+
+        let burn = wallet.burn::<2, 2>(Element::from(4u64), &tree).unwrap();
+        assert_satisfied(&burn);
+        assert_eq!(burn.utxo.kind, UtxoKind::Burn);
+
+        wallet.confirm(&burn);
+        assert_eq!(wallet.balance(), Element::from(6u64));
+    }
+
+    #[test]
+    fn notes_must_be_in_tree() {
+        let mut tree = Tree::new();
+        let wallet = funded_wallet(&[10], &mut tree);
+
+        assert!(matches!(
+            wallet.burn::<2, 2>(Element::from(10u64), &Tree::new()),
+            Err(Error::NoteNotInTree { .. })
+        ));
+    }
+
+    #[test]
+    fn mint_proof() {
+        let mut wallet = Wallet::new(Seed::random(&mut thread_rng()).spending_key());
+
+        let txn = wallet.mint::<2, 2>(Element::from(10u64));
+        let proof = txn.prove(&mut thread_rng()).unwrap();
+
+        assert!(proof.is_mint());
+        assert_eq!(proof.output_leaves[0], txn.utxo.outputs[0].commitment());
+        proof.verify().unwrap();
+
+        let memo = proof.output_memos[0].as_ref().unwrap();
+        assert_eq!(memo.commitment, proof.output_leaves[0]);
+        assert_eq!(proof.output_memos[1], None);
+
+        let txn = wallet.mint::<2, 3>(Element::from(10u64));
+        assert!(matches!(
+            txn.prove(&mut thread_rng()),
+            Err(Error::UnsupportedShape {
+                inputs: 2,
+                outputs: 3
+            })
+        ));
+    }
+}
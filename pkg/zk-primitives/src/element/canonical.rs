@@ -0,0 +1,148 @@
+use core::fmt;
+
+use ethnum::U256;
+
+use crate::{Base, Element};
+
+/// An error returned when a value is not in canonical form
+///
+/// See [`Element::canonicalize`] for more details on what the canonical form of an [`Element`] is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NonCanonicalError {
+    element: Element,
+}
+
+impl NonCanonicalError {
+    /// The non-canonical [`Element`] that caused this error
+    #[inline]
+    #[must_use]
+    pub fn element(&self) -> Element {
+        self.element
+    }
+}
+
+impl fmt::Display for NonCanonicalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "element {:x} is not canonical (it is not less than the field modulus)",
+            self.element
+        )
+    }
+}
+
+impl std::error::Error for NonCanonicalError {}
+
+impl Element {
+    /// Create an [`Element`] from a [`U256`], returning an error if it is not in canonical form
+    ///
+    /// ```rust
+    /// # use zk_primitives::*;
+    /// # use ethnum::U256;
+    /// assert_eq!(Element::new_canonical(U256::ONE), Ok(Element::ONE));
+    /// assert!(Element::new_canonical(Element::MODULUS.to_u256()).is_err());
+    /// ```
+    #[inline]
+    pub fn new_canonical(value: U256) -> Result<Self, NonCanonicalError> {
+        Self(value).check_canonical()
+    }
+
+    /// Convert big-endian bytes into an [`Element`], returning an error if it is not in
+    /// canonical form
+    ///
+    /// ```rust
+    /// # use zk_primitives::*;
+    /// assert_eq!(Element::from_be_bytes_canonical([0; 32]), Ok(Element::ZERO));
+    /// assert!(Element::from_be_bytes_canonical([0xff; 32]).is_err());
+    /// ```
+    #[inline]
+    pub fn from_be_bytes_canonical(bytes: [u8; 32]) -> Result<Self, NonCanonicalError> {
+        Self::from_be_bytes(bytes).check_canonical()
+    }
+
+    /// Return this [`Element`] unchanged if it is in canonical form, or an error if it isn't
+    ///
+    /// ```rust
+    /// # use zk_primitives::*;
+    /// assert_eq!(Element::ONE.check_canonical(), Ok(Element::ONE));
+    /// assert!(Element::MAX.check_canonical().is_err());
+    /// ```
+    #[inline]
+    pub fn check_canonical(self) -> Result<Self, NonCanonicalError> {
+        match self.is_canonical() {
+            true => Ok(self),
+            false => Err(NonCanonicalError { element: self }),
+        }
+    }
+
+    /// Convert this [`Element`] to its equivalent [`Base`] representation, returning an error
+    /// if it is not in canonical form
+    ///
+    /// Unlike [`Element::to_base`], this never silently reduces the value, so the resulting
+    /// [`Base`] always converts back to the same [`Element`]
+    #[inline]
+    pub fn to_base_checked(self) -> Result<Base, NonCanonicalError> {
+        self.check_canonical().map(Element::to_base)
+    }
+
+    /// Deserialize an [`Element`], rejecting values that are not in canonical form
+    ///
+    /// This accepts the same format as the [`Deserialize`][::serde::Deserialize] impl for
+    /// [`Element`], and is intended to be used with `#[serde(deserialize_with = "...")]`:
+    /// ```rust
+    /// # use zk_primitives::*;
+    /// #[derive(serde::Deserialize)]
+    /// struct Foo {
+    ///     #[serde(deserialize_with = "Element::deserialize_canonical")]
+    ///     element: Element,
+    /// }
+    ///
+    /// let modulus = serde_json::json!({ "element": Element::MODULUS.to_hex() });
+    /// assert!(serde_json::from_value::<Foo>(modulus).is_err());
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn deserialize_canonical<'de, D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: ::serde::Deserializer<'de>,
+    {
+        let element = <Self as ::serde::Deserialize>::deserialize(deserializer)?;
+        element
+            .check_canonical()
+            .map_err(::serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_strategy::proptest;
+
+    use super::*;
+
+    #[test]
+    fn modulus_is_not_canonical() {
+        assert!(Element::MODULUS.check_canonical().is_err());
+        assert!((Element::MODULUS - 1u64).check_canonical().is_ok());
+        assert!(Element::MAX.to_base_checked().is_err());
+    }
+
+    #[proptest]
+    fn check_canonical_agrees_with_is_canonical(element: Element) {
+        assert_eq!(element.check_canonical().is_ok(), element.is_canonical());
+    }
+
+    #[proptest]
+    fn checked_base_round_trips(mut element: Element) {
+        element.canonicalize();
+
+        let base = element.to_base_checked().unwrap();
+        assert_eq!(Element::from_base(base), element);
+    }
+
+    #[proptest]
+    fn deserialize_canonical_rejects_non_canonical(element: Element) {
+        let value = serde_json::to_value(element).unwrap();
+        let result = Element::deserialize_canonical(value);
+
+        assert_eq!(result.is_ok(), element.is_canonical());
+    }
+}
@@ -0,0 +1,148 @@
+use ff::Field;
+
+use super::halo2::u8s_to_u64;
+use crate::{Base, Element};
+
+/// Modular arithmetic in the prime field with modulus [`Element::MODULUS`]
+///
+/// The arithmetic operators on [`Element`] (`+`, `-`, `*`, etc.) act on the underlying 256-bit
+/// integer, which can differ from what a circuit computes. These methods instead convert both
+/// operands to a [`Base`], so the result always matches the equivalent [`Base`] operation. The
+/// output is always in canonical form.
+impl Element {
+    /// Add two elements modulo [`Element::MODULUS`]
+    ///
+    /// ```rust
+    /// # use zk_primitives::*;
+    /// let a = Element::MODULUS - 1u64;
+    ///
+    /// assert_eq!(a.field_add(Element::new(2)), Element::ONE);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn field_add(self, rhs: Element) -> Element {
+        Element::from_base(self.to_base() + rhs.to_base())
+    }
+
+    /// Subtract `rhs` from this element modulo [`Element::MODULUS`]
+    ///
+    /// ```rust
+    /// # use zk_primitives::*;
+    /// let a = Element::ZERO.field_sub(Element::ONE);
+    ///
+    /// assert_eq!(a, Element::MODULUS - 1u64);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn field_sub(self, rhs: Element) -> Element {
+        Element::from_base(self.to_base() - rhs.to_base())
+    }
+
+    /// Multiply two elements modulo [`Element::MODULUS`]
+    ///
+    /// ```rust
+    /// # use zk_primitives::*;
+    /// let minus_one = Element::MODULUS - 1u64;
+    ///
+    /// assert_eq!(minus_one.field_mul(minus_one), Element::ONE);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn field_mul(self, rhs: Element) -> Element {
+        Element::from_base(self.to_base() * rhs.to_base())
+    }
+
+    /// The additive inverse of this element modulo [`Element::MODULUS`]
+    ///
+    /// ```rust
+    /// # use zk_primitives::*;
+    /// assert_eq!(Element::ONE.field_neg(), Element::MODULUS - 1u64);
+    /// assert_eq!(Element::ZERO.field_neg(), Element::ZERO);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn field_neg(self) -> Element {
+        Element::from_base(-self.to_base())
+    }
+
+    /// The multiplicative inverse of this element modulo [`Element::MODULUS`]
+    ///
+    /// Returns `None` if this element is congruent to zero
+    ///
+    /// ```rust
+    /// # use zk_primitives::*;
+    /// let a = Element::new(5);
+    /// let inverse = a.field_inverse().unwrap();
+    ///
+    /// assert_eq!(a.field_mul(inverse), Element::ONE);
+    /// assert_eq!(Element::ZERO.field_inverse(), None);
+    /// assert_eq!(Element::MODULUS.field_inverse(), None);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn field_inverse(self) -> Option<Element> {
+        Option::from(self.to_base().invert()).map(Element::from_base)
+    }
+
+    /// Raise this element to the power `exp` modulo [`Element::MODULUS`]
+    ///
+    /// The exponent is treated as a plain 256-bit integer (i.e. it is not reduced)
+    ///
+    /// ```rust
+    /// # use zk_primitives::*;
+    /// let a = Element::new(3);
+    ///
+    /// assert_eq!(a.field_pow(Element::new(4)), Element::new(81));
+    /// assert_eq!(a.field_pow(Element::ZERO), Element::ONE);
+    ///
+    /// // Fermat's little theorem
+    /// assert_eq!(a.field_pow(Element::MODULUS - 1u64), Element::ONE);
+    /// ```
+    #[inline]
+    #[must_use]
+    pub fn field_pow(self, exp: Element) -> Element {
+        let exp = u8s_to_u64(exp.to_le_bytes());
+        Element::from_base(self.to_base().pow_vartime(exp))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_strategy::proptest;
+
+    use super::*;
+
+    #[proptest]
+    fn field_ops_match_base(a: Element, b: Element) {
+        let (x, y) = (a.to_base(), b.to_base());
+
+        assert_eq!(a.field_add(b).to_base(), x + y);
+        assert_eq!(a.field_sub(b).to_base(), x - y);
+        assert_eq!(a.field_mul(b).to_base(), x * y);
+        assert_eq!(a.field_neg().to_base(), -x);
+    }
+
+    #[proptest]
+    fn field_ops_are_canonical(a: Element, b: Element) {
+        assert!(a.field_add(b).is_canonical());
+        assert!(a.field_sub(b).is_canonical());
+        assert!(a.field_mul(b).is_canonical());
+        assert!(a.field_neg().is_canonical());
+        assert!(a.field_pow(b).is_canonical());
+    }
+
+    #[proptest]
+    fn field_inverse_is_inverse(a: Element) {
+        match a.field_inverse() {
+            None => assert_eq!(a.to_base(), Base::zero()),
+            Some(inverse) => assert_eq!(a.field_mul(inverse), Element::ONE),
+        }
+    }
+
+    #[proptest]
+    fn field_pow_matches_repeated_mul(a: Element, #[strategy(0u64..16)] exp: u64) {
+        let expected = (0..exp).fold(Element::ONE, |acc, _| acc.field_mul(a));
+
+        assert_eq!(a.field_pow(Element::new(exp)), expected);
+    }
+}
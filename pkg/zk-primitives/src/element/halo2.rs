@@ -81,7 +81,7 @@ impl From<Element> for Base {
     }
 }
 
-fn u8s_to_u64(u8s: [u8; 32]) -> [u64; 4] {
+pub(super) fn u8s_to_u64(u8s: [u8; 32]) -> [u64; 4] {
     [
         u64::from_le_bytes((&u8s[0..8]).try_into().unwrap()),
         u64::from_le_bytes((&u8s[8..16]).try_into().unwrap()),
@@ -1,8 +1,10 @@
 use ethnum::U256;
 
 mod arith;
+mod canonical;
 mod collision;
 mod convert;
+mod field;
 mod fmt;
 mod halo2;
 mod lsb;
@@ -15,6 +17,7 @@ mod rand_impls;
 #[cfg(feature = "rand")]
 pub use rand_impls::Insecure;
 
+pub use canonical::NonCanonicalError;
 pub use lsb::Lsb;
 
 #[cfg(feature = "serde")]
@@ -27,6 +30,10 @@ mod serde;
 /// However, in a ZK context, it is usually converted to a [`Base`], which is an integer modulo
 /// "some large prime". This restricts the set of usable values to something approximating a `u254`
 ///
+/// The arithmetic operators act on the underlying integer. For arithmetic that matches [`Base`],
+/// use the `field_*` methods (e.g. [`Element::field_add`]). To reject values that are not in
+/// canonical form, use [`Element::check_canonical`] and friends.
+///
 /// [`Base`]: crate::Base
 #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Hash)]
 #[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
@@ -51,6 +58,10 @@ impl Element {
     /// The [`Element`] used as the initialization vector when hashing bytes
     pub(crate) const BYTE_HASH_IV: Self = Self(U256::new(2));
 
+    /// The [`Element`] used as the initialization vector when hashing bytes with
+    /// [`hash_bytes_v2`][crate::hash_bytes_v2]
+    pub(crate) const BYTE_HASH_V2_IV: Self = Self(U256::new(3));
+
     /// Create a new [`Element`] from a u64
     ///
     /// This is largely provided to help type inference in simple cases
@@ -0,0 +1,300 @@
+use core::{fmt, str::FromStr};
+
+use bech32::{FromBase32, ToBase32, Variant};
+
+use crate::{Element, NonCanonicalError};
+
+/// An error produced when decoding a checksummed [`Element`] string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The string was not valid bech32, or its checksum was incorrect
+    Bech32(bech32::Error),
+    /// The string was valid bech32, but not bech32m
+    WrongVariant,
+    /// The human-readable prefix didn't match the kind of value being decoded
+    WrongPrefix {
+        /// The prefix required by the kind of value being decoded
+        expected: &'static str,
+        /// The prefix found in the string
+        found: String,
+    },
+    /// The string didn't contain exactly 32 bytes of data
+    WrongLength(usize),
+    /// The decoded value was not in canonical form
+    NonCanonical(NonCanonicalError),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bech32(e) => write!(f, "invalid bech32m string: {e}"),
+            Self::WrongVariant => write!(f, "expected bech32m, but found bech32"),
+            Self::WrongPrefix { expected, found } => {
+                write!(f, "expected prefix `{expected}`, but found `{found}`")
+            }
+            Self::WrongLength(len) => write!(f, "expected 32 bytes, but found {len}"),
+            Self::NonCanonical(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Bech32(e) => Some(e),
+            Self::NonCanonical(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<bech32::Error> for DecodeError {
+    fn from(e: bech32::Error) -> Self {
+        Self::Bech32(e)
+    }
+}
+
+/// Encode 32 bytes as a bech32m string with the human-readable prefix `hrp`
+///
+/// This is for values that aren't [`Element`]s (e.g. keys on another curve), elements should use
+/// [`Element::to_bech32m`]
+///
+/// # Panics
+///
+/// Panics if `hrp` is not a valid bech32 human-readable prefix
+#[must_use]
+pub fn encode_bech32m(hrp: &str, bytes: [u8; 32]) -> String {
+    bech32::encode(hrp, bytes.to_base32(), Variant::Bech32m).expect("invalid human-readable prefix")
+}
+
+/// Decode a bech32m string produced by [`encode_bech32m`]
+///
+/// This fails if the checksum is invalid, the prefix is not `hrp`, or the string doesn't contain
+/// exactly 32 bytes
+pub fn decode_bech32m(hrp: &'static str, s: &str) -> Result<[u8; 32], DecodeError> {
+    let (found, data, variant) = bech32::decode(s)?;
+
+    if found != hrp {
+        return Err(DecodeError::WrongPrefix {
+            expected: hrp,
+            found,
+        });
+    }
+
+    if variant != Variant::Bech32m {
+        return Err(DecodeError::WrongVariant);
+    }
+
+    let bytes = Vec::<u8>::from_base32(&data)?;
+    <[u8; 32]>::try_from(bytes).map_err(|v| DecodeError::WrongLength(v.len()))
+}
+
+impl Element {
+    /// Encode this [`Element`] as a bech32m string with the human-readable prefix `hrp`
+    ///
+    /// The checksum means that most typos will be detected when decoding with
+    /// [`Element::from_bech32m`]. Prefer the typed wrappers ([`Address`], [`NoteCommitment`]),
+    /// which use a fixed prefix per kind of value
+    ///
+    /// ```rust
+    /// # use zk_primitives::*;
+    /// let encoded = Element::new(1234).to_bech32m("test");
+    /// assert_eq!(Element::from_bech32m("test", &encoded), Ok(Element::new(1234)));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `hrp` is not a valid bech32 human-readable prefix
+    #[must_use]
+    pub fn to_bech32m(self, hrp: &str) -> String {
+        encode_bech32m(hrp, self.to_be_bytes())
+    }
+
+    /// Decode a bech32m string produced by [`Element::to_bech32m`]
+    ///
+    /// This fails if the checksum is invalid, the prefix is not `hrp`, or the decoded value is not
+    /// in canonical form
+    ///
+    /// ```rust
+    /// # use zk_primitives::*;
+    /// let mut encoded = Element::new(1234).to_bech32m("test");
+    ///
+    /// // introduce a typo
+    /// let last = encoded.pop().unwrap();
+    /// encoded.push(if last == 'q' { 'p' } else { 'q' });
+    ///
+    /// assert!(Element::from_bech32m("test", &encoded).is_err());
+    /// ```
+    pub fn from_bech32m(hrp: &'static str, s: &str) -> Result<Self, DecodeError> {
+        let bytes = decode_bech32m(hrp, s)?;
+
+        Self::from_be_bytes_canonical(bytes).map_err(DecodeError::NonCanonical)
+    }
+}
+
+/// Define a wrapper around an [`Element`] that is displayed as a bech32m string with a fixed
+/// human-readable prefix
+macro_rules! bech32m_type {
+    ($(#[$meta:meta])* $name:ident, $hrp:literal) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+        pub struct $name(pub Element);
+
+        impl $name {
+            /// The human-readable prefix of the bech32m encoding of this type
+            pub const HRP: &'static str = $hrp;
+
+            /// The wrapped [`Element`]
+            #[inline]
+            #[must_use]
+            pub fn element(self) -> Element {
+                self.0
+            }
+        }
+
+        impl From<Element> for $name {
+            #[inline]
+            fn from(element: Element) -> Self {
+                Self(element)
+            }
+        }
+
+        impl From<$name> for Element {
+            #[inline]
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0.to_bech32m(Self::HRP))
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = DecodeError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Element::from_bech32m(Self::HRP, s).map(Self)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl ::serde::Serialize for $name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: ::serde::Serializer,
+            {
+                serializer.collect_str(self)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de> ::serde::Deserialize<'de> for $name {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: ::serde::Deserializer<'de>,
+            {
+                let s = <String as ::serde::Deserialize>::deserialize(deserializer)?;
+                s.parse().map_err(::serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+bech32m_type!(
+    /// The address of a note owner, encoded as a bech32m string with the prefix `payyaddr`
+    ///
+    /// ```rust
+    /// # use zk_primitives::*;
+    /// let address = Address(Element::new(1));
+    /// let encoded = address.to_string();
+    ///
+    /// assert!(encoded.starts_with("payyaddr1"));
+    /// assert_eq!(encoded.parse(), Ok(address));
+    ///
+    /// // a string encoding a different kind of value is rejected
+    /// let commitment = NoteCommitment(Element::new(1)).to_string();
+    /// assert!(commitment.parse::<Address>().is_err());
+    /// ```
+    Address,
+    "payyaddr"
+);
+
+bech32m_type!(
+    /// A note commitment, encoded as a bech32m string with the prefix `payynote`
+    NoteCommitment,
+    "payynote"
+);
+
+#[cfg(test)]
+mod tests {
+    use test_strategy::proptest;
+
+    use super::*;
+
+    #[proptest]
+    fn round_trip(mut element: Element) {
+        element.canonicalize();
+
+        let address = Address(element);
+        assert_eq!(address.to_string().parse(), Ok(address));
+
+        let value = serde_json::to_value(address).unwrap();
+        assert_eq!(serde_json::from_value::<Address>(value).unwrap(), address);
+    }
+
+    #[proptest]
+    fn single_character_typo_is_rejected(
+        mut element: Element,
+        #[strategy(0usize..52)] index: usize,
+    ) {
+        element.canonicalize();
+
+        let encoded = Address(element).to_string();
+        let data_start = Address::HRP.len() + 1;
+        let index = data_start + index;
+
+        let mut chars: Vec<char> = encoded.chars().collect();
+        chars[index] = if chars[index] == 'q' { 'p' } else { 'q' };
+        let typo: String = chars.into_iter().collect();
+
+        assert!(typo.parse::<Address>().is_err());
+    }
+
+    #[test]
+    fn wrong_prefix() {
+        let encoded = Address(Element::ONE).to_string();
+
+        assert_eq!(
+            encoded.parse::<NoteCommitment>(),
+            Err(DecodeError::WrongPrefix {
+                expected: "payynote",
+                found: "payyaddr".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn non_canonical_is_rejected() {
+        let encoded = Element::MODULUS.to_bech32m(Address::HRP);
+
+        assert!(matches!(
+            encoded.parse::<Address>(),
+            Err(DecodeError::NonCanonical(_))
+        ));
+    }
+
+    #[test]
+    fn bech32_variant_is_rejected() {
+        let encoded = bech32::encode(
+            Address::HRP,
+            Element::ONE.to_be_bytes().to_base32(),
+            Variant::Bech32,
+        )
+        .unwrap();
+
+        assert_eq!(encoded.parse::<Address>(), Err(DecodeError::WrongVariant));
+    }
+}
@@ -83,6 +83,15 @@ pub fn hash_merge<const N: usize>(elements: [Element; N]) -> Element {
 ///
 /// assert_ne!(hash_1, hash_2);
 /// ```
+///
+/// Note that the final chunk is padded with zeroes and the length is not part of the hash, so
+/// inputs that differ only by trailing zeroes hash to the same value:
+/// ```rust
+/// # use zk_primitives::*;
+/// assert_eq!(hash_bytes(&[1]), hash_bytes(&[1, 0]));
+/// ```
+/// This function is kept for compatibility with values that have already been hashed. New code
+/// should use [`hash_bytes_v2`], which doesn't have this problem.
 #[inline]
 #[must_use]
 pub fn hash_bytes(bytes: &[u8]) -> Element {
@@ -110,10 +119,61 @@ fn bytes_to_element(bytes: &[u8]) -> Element {
     u128::from_be_bytes(padded_bytes).into()
 }
 
+/// The number of bytes packed into each [`Element`] by [`hash_bytes_v2`]
+///
+/// 31 bytes is the largest whole number of bytes that always fits below [`Element::MODULUS`]
+const BYTES_PER_ELEMENT_V2: usize = 31;
+
+/// Hash a slice of bytes, including its length
+///
+/// Unlike [`hash_bytes`], the length of the input is hashed before its contents, so inputs of
+/// different lengths never hash to the same value (barring a collision in the underlying hash
+/// function). The bytes are also packed more tightly, 31 bytes per [`Element`], so fewer calls
+/// to [`hash_merge`] are needed.
+///
+/// ```rust
+/// # use zk_primitives::*;
+/// let hash_1 = hash_bytes_v2(&[1, 2, 3, 4]);
+/// let hash_2 = hash_bytes_v2(&[1, 2, 3, 5]);
+///
+/// assert_ne!(hash_1, hash_2);
+///
+/// // trailing zeroes change the hash
+/// assert_ne!(hash_bytes_v2(&[1]), hash_bytes_v2(&[1, 0]));
+/// ```
+#[inline]
+#[must_use]
+pub fn hash_bytes_v2(bytes: &[u8]) -> Element {
+    let initial = Element::BYTE_HASH_V2_IV;
+    let length = Element::from(bytes.len() as u128);
+
+    let elements_from_bytes = bytes
+        .chunks(BYTES_PER_ELEMENT_V2)
+        .map(packed_bytes_to_element);
+
+    [initial, length]
+        .into_iter()
+        .chain(elements_from_bytes)
+        .reduce(|left, right| hash_merge([left, right]))
+        .unwrap() // there are always at least 2 elements
+}
+
+/// Convert a slice of bytes with length in the range `1..=31` to a canonical [`Element`]
+///
+/// The most significant byte of the element is always zero. If there are fewer than 31 bytes, the
+/// lower bytes are padded with zeroes
+fn packed_bytes_to_element(bytes: &[u8]) -> Element {
+    let mut padded_bytes = [0; 32];
+    padded_bytes[1..=bytes.len()].copy_from_slice(bytes);
+    Element::from_be_bytes(padded_bytes)
+}
+
 #[cfg(test)]
 mod tests {
+    use proptest::{prop_assert_ne, prop_assume};
     use rand::Rng;
     use rand_chacha::{rand_core::SeedableRng, ChaChaRng};
+    use test_strategy::proptest;
 
     use super::*;
 
@@ -197,4 +257,34 @@ mod tests {
 
         insta::assert_json_snapshot!(results);
     }
+
+    #[test]
+    fn hash_bytes_v2_includes_length() {
+        assert_ne!(hash_bytes_v2(&[]), hash_bytes_v2(&[0]));
+        assert_ne!(hash_bytes_v2(&[1]), hash_bytes_v2(&[1, 0]));
+        assert_ne!(hash_bytes_v2(&[0; 31]), hash_bytes_v2(&[0; 32]));
+        assert_ne!(hash_bytes_v2(&[0; 16]), hash_bytes_v2(&[0; 31]));
+    }
+
+    #[test]
+    fn hash_bytes_v2_differs_from_v1() {
+        for bytes in [&[][..], &[0], &[1, 2, 3], &[0xff; 64]] {
+            assert_ne!(hash_bytes(bytes), hash_bytes_v2(bytes));
+        }
+    }
+
+    #[test]
+    fn packed_bytes_are_canonical() {
+        let element = packed_bytes_to_element(&[0xff; BYTES_PER_ELEMENT_V2]);
+        assert!(element.is_canonical());
+
+        let element = packed_bytes_to_element(&[1]);
+        assert_eq!(element, Element::ONE << 240u8);
+    }
+
+    #[proptest]
+    fn hash_bytes_v2_distinct_inputs(a: Vec<u8>, b: Vec<u8>) {
+        prop_assume!(a != b);
+        prop_assert_ne!(hash_bytes_v2(&a), hash_bytes_v2(&b));
+    }
 }
@@ -10,7 +10,9 @@
 //! A set of core primitives for use with polybase's zk circuits
 
 mod element;
+mod encoding;
 mod hash;
+mod merkle_proof;
 mod path;
 
 #[cfg(feature = "test-api")]
@@ -18,8 +20,10 @@ pub use hash::{hash_count, hash_element_count, reset_hash_count, reset_hash_elem
 
 #[cfg(feature = "rand")]
 pub use element::Insecure;
-pub use element::{Element, Lsb};
-pub use hash::{hash_bytes, hash_merge};
+pub use element::{Element, Lsb, NonCanonicalError};
+pub use encoding::{decode_bech32m, encode_bech32m, Address, DecodeError, NoteCommitment};
+pub use hash::{hash_bytes, hash_bytes_v2, hash_merge};
+pub use merkle_proof::{MerkleProof, ProofError};
 pub use path::compute_merkle_root;
 
 /// The base element used by cryptographic operations on this tree
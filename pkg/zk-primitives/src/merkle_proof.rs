@@ -0,0 +1,468 @@
+use core::fmt;
+
+use crate::{hash_merge, Element};
+
+/// A Merkle proof for a slot in a tree of depth `DEPTH`
+///
+/// A [`MerkleProof`] carries everything needed to recompute the root hash of a tree: the leaf, the
+/// left/right direction at each level, and the sibling at each level. Like
+/// [`compute_merkle_root`][crate::compute_merkle_root], the directions and siblings are stored in
+/// "deepest-first" order, and a tree of depth `DEPTH` has `DEPTH - 1` siblings. `DEPTH` must be at
+/// least 1, which is checked at compile time.
+///
+/// The directions are derived from the [`Lsb`][crate::Lsb] of a key. For an inclusion proof, the
+/// key is the leaf itself, but for a proof that a slot is empty, the leaf is
+/// [`Element::NULL_HASH`], so the directions must come from the key that maps to that slot (see
+/// [`MerkleProof::for_key`]).
+///
+/// ```rust
+/// # use zk_primitives::*;
+/// let a = hash_merge([Element::new(0), Element::new(1)]);
+/// let b = hash_merge([Element::new(2), Element::new(3)]);
+/// let root = hash_merge([a, b]);
+///
+/// // 2 is 0b10, so it's on the left at the deepest level, then on the right
+/// let proof = MerkleProof::<3>::new(Element::new(2), vec![Element::new(3), a]).unwrap();
+///
+/// assert_eq!(proof.directions(), [false, true]);
+/// assert_eq!(proof.verify(root), Ok(()));
+/// assert!(proof.verify(a).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(::serde::Serialize, ::serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(try_from = "MerkleProofRepr", into = "MerkleProofRepr")
+)]
+pub struct MerkleProof<const DEPTH: usize> {
+    leaf: Element,
+    /// `true` means the node is on the right (i.e. the sibling is on the left)
+    directions: Vec<bool>,
+    siblings: Vec<Element>,
+}
+
+/// The reasons that a [`MerkleProof`] can be invalid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofError {
+    /// The proof had the wrong number of siblings for its depth
+    WrongSiblingCount {
+        /// The number of siblings required by the depth of the proof
+        expected: usize,
+        /// The number of siblings that were provided
+        actual: usize,
+    },
+    /// The number of directions didn't match the number of siblings
+    WrongDirectionCount {
+        /// The number of siblings in the proof
+        expected: usize,
+        /// The number of directions that were provided
+        actual: usize,
+    },
+    /// The sibling at `level` was not in canonical form, so it would be silently reduced when
+    /// hashed
+    NonCanonicalSibling {
+        /// The level of the offending sibling, where `0` is the deepest level
+        level: usize,
+    },
+    /// The direction at `level` didn't match the corresponding bit of the key
+    DirectionMismatch {
+        /// The level of the offending direction, where `0` is the deepest level
+        level: usize,
+    },
+    /// The hash computed at `level` didn't match the expected hash
+    ///
+    /// [`MerkleProof::verify`] only knows the root hash, so it reports the root level (`DEPTH -
+    /// 1`), while [`MerkleProof::verify_hashes`] reports the deepest level that diverges
+    HashMismatch {
+        /// The level of the hash, where `0` is the leaf and `DEPTH - 1` is the root (the same
+        /// order as [`MerkleProof::hashes`])
+        level: usize,
+        /// The hash that the proof was checked against
+        expected: Element,
+        /// The hash computed from the proof
+        computed: Element,
+    },
+}
+
+impl fmt::Display for ProofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WrongSiblingCount { expected, actual } => {
+                write!(f, "expected {expected} siblings, but found {actual}")
+            }
+            Self::WrongDirectionCount { expected, actual } => {
+                write!(f, "expected {expected} directions, but found {actual}")
+            }
+            Self::NonCanonicalSibling { level } => {
+                write!(f, "the sibling at level {level} is not canonical")
+            }
+            Self::DirectionMismatch { level } => {
+                write!(f, "the direction at level {level} doesn't match the key")
+            }
+            Self::HashMismatch {
+                level,
+                expected,
+                computed,
+            } => {
+                write!(
+                    f,
+                    "expected hash {expected} at level {level}, but computed {computed}"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProofError {}
+
+impl<const DEPTH: usize> MerkleProof<DEPTH> {
+    /// A tree has at least one level (its root), so `DEPTH - 1` can't underflow
+    ///
+    /// This is evaluated when a constructor is monomorphized, so `MerkleProof<0>` fails to compile
+    const DEPTH_IS_NONZERO: () =
+        assert!(DEPTH >= 1, "a merkle tree must have a depth of at least 1");
+
+    /// Create a proof that `leaf` is present in a tree, using its own least significant bits as
+    /// directions
+    ///
+    /// `siblings` must be in "deepest-first" order, and must contain `DEPTH - 1` elements
+    #[inline]
+    pub fn new(leaf: Element, siblings: Vec<Element>) -> Result<Self, ProofError> {
+        Self::for_key(leaf, leaf, siblings)
+    }
+
+    /// Create a proof for the slot of `key`, containing `leaf`
+    ///
+    /// The directions are derived from the `DEPTH - 1` least significant bits of `key`. This is
+    /// useful for proving that the slot of `key` is empty, by passing [`Element::NULL_HASH`] as
+    /// `leaf`
+    ///
+    /// ```rust
+    /// # use zk_primitives::*;
+    /// let root = hash_merge([Element::NULL_HASH, Element::new(3)]);
+    ///
+    /// let key = Element::new(2);
+    /// let proof = MerkleProof::<2>::for_key(key, Element::NULL_HASH, vec![Element::new(3)]).unwrap();
+    ///
+    /// assert_eq!(proof.verify(root), Ok(()));
+    /// ```
+    pub fn for_key(
+        key: Element,
+        leaf: Element,
+        siblings: Vec<Element>,
+    ) -> Result<Self, ProofError> {
+        let () = Self::DEPTH_IS_NONZERO;
+
+        let directions = key.lsb(DEPTH - 1).into_iter().rev().collect();
+        Self::from_parts(leaf, directions, siblings)
+    }
+
+    /// Create a proof from its raw parts
+    ///
+    /// Unlike [`MerkleProof::for_key`], `directions` are not checked against any key, only that
+    /// there is one per sibling
+    pub fn from_parts(
+        leaf: Element,
+        directions: Vec<bool>,
+        siblings: Vec<Element>,
+    ) -> Result<Self, ProofError> {
+        let () = Self::DEPTH_IS_NONZERO;
+
+        if siblings.len() != DEPTH - 1 {
+            return Err(ProofError::WrongSiblingCount {
+                expected: DEPTH - 1,
+                actual: siblings.len(),
+            });
+        }
+
+        if directions.len() != siblings.len() {
+            return Err(ProofError::WrongDirectionCount {
+                expected: siblings.len(),
+                actual: directions.len(),
+            });
+        }
+
+        Ok(Self {
+            leaf,
+            directions,
+            siblings,
+        })
+    }
+
+    /// The leaf that this proof proves the presence of
+    #[inline]
+    #[must_use]
+    pub fn leaf(&self) -> Element {
+        self.leaf
+    }
+
+    /// The direction at each level, deepest first
+    ///
+    /// `true` means the node is on the right (i.e. the sibling is on the left)
+    #[inline]
+    #[must_use]
+    pub fn directions(&self) -> &[bool] {
+        &self.directions
+    }
+
+    /// The sibling at each level, deepest first
+    #[inline]
+    #[must_use]
+    pub fn siblings(&self) -> &[Element] {
+        &self.siblings
+    }
+
+    /// Check that the directions of this proof match the least significant bits of `key`
+    ///
+    /// ```rust
+    /// # use zk_primitives::*;
+    /// let proof = MerkleProof::<3>::new(Element::new(2), vec![Element::ZERO; 2]).unwrap();
+    ///
+    /// assert_eq!(proof.check_key(Element::new(2)), Ok(()));
+    /// assert_eq!(
+    ///     proof.check_key(Element::new(3)),
+    ///     Err(ProofError::DirectionMismatch { level: 0 }),
+    /// );
+    /// ```
+    pub fn check_key(&self, key: Element) -> Result<(), ProofError> {
+        let bits = key.lsb(DEPTH - 1).into_iter().rev();
+
+        match self
+            .directions
+            .iter()
+            .zip(bits)
+            .position(|(&direction, bit)| direction != bit)
+        {
+            Some(level) => Err(ProofError::DirectionMismatch { level }),
+            None => Ok(()),
+        }
+    }
+
+    /// Compute the root hash of the tree described by this proof
+    #[must_use]
+    pub fn compute_root(&self) -> Element {
+        *self.hashes().last().unwrap()
+    }
+
+    /// The hash of the node at each level, starting with the leaf and ending with the root hash
+    ///
+    /// This can be compared with the hashes from a known tree to find the level at which a proof
+    /// diverges
+    #[must_use]
+    pub fn hashes(&self) -> Vec<Element> {
+        let mut hashes = Vec::with_capacity(DEPTH);
+        hashes.push(self.leaf);
+
+        let mut hash = self.leaf;
+
+        for (&sibling, &direction) in self.siblings.iter().zip(&self.directions) {
+            hash = match direction {
+                false => hash_merge([hash, sibling]),
+                true => hash_merge([sibling, hash]),
+            };
+
+            hashes.push(hash);
+        }
+
+        hashes
+    }
+
+    /// Verify that this proof produces the root hash `root`
+    ///
+    /// If this proof is invalid, the returned [`ProofError`] explains why. Siblings which are not
+    /// in canonical form are rejected, since they would be silently reduced when hashed
+    pub fn verify(&self, root: Element) -> Result<(), ProofError> {
+        self.check_siblings()?;
+
+        let computed = self.compute_root();
+
+        match computed == root {
+            true => Ok(()),
+            false => Err(ProofError::HashMismatch {
+                level: DEPTH - 1,
+                expected: root,
+                computed,
+            }),
+        }
+    }
+
+    /// Verify this proof against the hash of each node on its path in a known tree, leaf first
+    /// and root last
+    ///
+    /// Unlike [`MerkleProof::verify`], this finds the level at which the proof diverges from the
+    /// tree: a wrong sibling at level `i` makes the hash at level `i + 1` (and every level above
+    /// it) wrong, so the deepest mismatching hash is reported
+    ///
+    /// ```rust
+    /// # use zk_primitives::*;
+    /// let a = hash_merge([Element::new(0), Element::new(1)]);
+    /// let b = hash_merge([Element::new(2), Element::new(3)]);
+    /// let root = hash_merge([a, b]);
+    ///
+    /// let proof = MerkleProof::<3>::new(Element::new(2), vec![Element::new(4), a]).unwrap();
+    ///
+    /// assert_eq!(
+    ///     proof.verify_hashes(&[Element::new(2), b, root]),
+    ///     Err(ProofError::HashMismatch {
+    ///         level: 1,
+    ///         expected: b,
+    ///         computed: hash_merge([Element::new(2), Element::new(4)]),
+    ///     }),
+    /// );
+    /// ```
+    pub fn verify_hashes(&self, expected: &[Element; DEPTH]) -> Result<(), ProofError> {
+        self.check_siblings()?;
+
+        let mismatch = self
+            .hashes()
+            .into_iter()
+            .zip(expected)
+            .enumerate()
+            .find(|(_, (computed, expected))| computed != *expected);
+
+        match mismatch {
+            None => Ok(()),
+            Some((level, (computed, &expected))) => Err(ProofError::HashMismatch {
+                level,
+                expected,
+                computed,
+            }),
+        }
+    }
+
+    /// Siblings which are not in canonical form would be silently reduced when hashed
+    fn check_siblings(&self) -> Result<(), ProofError> {
+        match self.siblings.iter().position(|s| !s.is_canonical()) {
+            Some(level) => Err(ProofError::NonCanonicalSibling { level }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The serialized form of a [`MerkleProof`], which is validated when deserializing
+#[cfg(feature = "serde")]
+#[derive(::serde::Serialize, ::serde::Deserialize)]
+struct MerkleProofRepr {
+    leaf: Element,
+    directions: Vec<bool>,
+    siblings: Vec<Element>,
+}
+
+#[cfg(feature = "serde")]
+impl<const DEPTH: usize> TryFrom<MerkleProofRepr> for MerkleProof<DEPTH> {
+    type Error = ProofError;
+
+    fn try_from(repr: MerkleProofRepr) -> Result<Self, Self::Error> {
+        Self::from_parts(repr.leaf, repr.directions, repr.siblings)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<const DEPTH: usize> From<MerkleProof<DEPTH>> for MerkleProofRepr {
+    fn from(proof: MerkleProof<DEPTH>) -> Self {
+        Self {
+            leaf: proof.leaf,
+            directions: proof.directions,
+            siblings: proof.siblings,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_strategy::proptest;
+
+    use super::*;
+    use crate::compute_merkle_root;
+
+    #[proptest]
+    fn agrees_with_compute_merkle_root(leaf: Element, siblings: [Element; 7]) {
+        let proof = MerkleProof::<8>::new(leaf, siblings.to_vec()).unwrap();
+        let bits = leaf.lsb(7).into_iter().rev();
+        let expected = compute_merkle_root(leaf, siblings.into_iter().zip(bits));
+
+        assert_eq!(proof.compute_root(), expected);
+        assert_eq!(proof.hashes().len(), 8);
+    }
+
+    #[test]
+    fn wrong_sibling_count() {
+        let result = MerkleProof::<4>::new(Element::ONE, vec![Element::ZERO; 2]);
+
+        assert_eq!(
+            result,
+            Err(ProofError::WrongSiblingCount {
+                expected: 3,
+                actual: 2
+            })
+        );
+    }
+
+    #[test]
+    fn depth_one_is_just_the_leaf() {
+        let proof = MerkleProof::<1>::new(Element::ONE, vec![]).unwrap();
+
+        assert_eq!(proof.compute_root(), Element::ONE);
+        assert_eq!(proof.verify(Element::ONE), Ok(()));
+    }
+
+    #[test]
+    fn non_canonical_sibling_reports_level() {
+        let mut siblings = vec![Element::ZERO; 3];
+        siblings[1] = Element::MODULUS;
+
+        let proof = MerkleProof::<4>::new(Element::ONE, siblings).unwrap();
+
+        assert_eq!(
+            proof.verify(proof.compute_root()),
+            Err(ProofError::NonCanonicalSibling { level: 1 })
+        );
+    }
+
+    #[test]
+    fn root_mismatch() {
+        let proof = MerkleProof::<4>::new(Element::ONE, vec![Element::ZERO; 3]).unwrap();
+        let computed = proof.compute_root();
+
+        assert_eq!(proof.verify(computed), Ok(()));
+        assert_eq!(
+            proof.verify(Element::ONE),
+            Err(ProofError::HashMismatch {
+                level: 3,
+                expected: Element::ONE,
+                computed
+            })
+        );
+    }
+
+    #[test]
+    fn hash_mismatch_reports_deepest_level() {
+        let proof = MerkleProof::<4>::new(Element::ONE, vec![Element::ZERO; 3]).unwrap();
+        let hashes: [Element; 4] = proof.hashes().try_into().unwrap();
+        assert_eq!(proof.verify_hashes(&hashes), Ok(()));
+
+        let mut siblings = vec![Element::ZERO; 3];
+        siblings[1] = Element::ONE;
+        let wrong = MerkleProof::<4>::new(Element::ONE, siblings).unwrap();
+
+        assert_eq!(
+            wrong.verify_hashes(&hashes),
+            Err(ProofError::HashMismatch {
+                level: 2,
+                expected: hashes[2],
+                computed: wrong.hashes()[2],
+            })
+        );
+    }
+
+    #[test]
+    fn deserialize_validates_length() {
+        let proof = MerkleProof::<4>::new(Element::ONE, vec![Element::ZERO; 3]).unwrap();
+        let value = serde_json::to_value(&proof).unwrap();
+
+        let proof_again: MerkleProof<4> = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(proof, proof_again);
+
+        assert!(serde_json::from_value::<MerkleProof<5>>(value).is_err());
+    }
+}